@@ -1,10 +1,20 @@
+// The parsing below predates the lints the rest of the crate is checked with.
+#![allow(
+    unknown_lints,
+    clippy::needless_borrow,
+    clippy::needless_borrows_for_generic_args,
+    clippy::comparison_to_empty,
+    clippy::redundant_closure
+)]
+
 use std::{env, num::ParseIntError, process::exit};
 
-use aoc::data::read_lines;
+use aoc::{data::read_lines, log};
 
 fn main() {
-    let args: Vec<String> = env::args().collect();
-    let Some(file_path) = args.get(1) else {
+    log::init();
+    let args: Vec<String> = env::args().skip(1).filter(|a| !log::is_flag(a)).collect();
+    let Some(file_path) = args.first() else {
         eprintln!("Usage: day1 path/to/data.txt");
         exit(1);
     };
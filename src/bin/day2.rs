@@ -1,73 +1,223 @@
-use aoc::data::read_lines;
+use std::{
+    collections::{BTreeMap, HashMap},
+    convert::Infallible,
+    env,
+    fmt::Display,
+    fs,
+    io::{self, BufRead, Write},
+    process::exit,
+};
+
+use aoc::{data::read_lines, info, log, trace};
 use once_cell::sync::Lazy;
 use regex::Regex;
 use thiserror::Error;
 
 pub fn main() {
-    let data = read_lines("inputs/day2.txt", Round::parse).expect("could not parse the data?");
-    let sum: u32 = data.iter().map(|r| r.score()).sum();
+    log::init();
+    let rules = match env::args().find_map(|a| a.strip_prefix("--rules=").map(String::from)) {
+        None => Rules::classic(),
+        Some(name) => Rules::by_name(&name).unwrap_or_else(|| {
+            eprintln!("unknown rules {:?}, try classic or lizard-spock", name);
+            exit(1);
+        }),
+    };
+    let report = env::args().any(|a| a == "--report");
+    let mapping = env::args()
+        .find_map(|a| a.strip_prefix("--mapping=").map(String::from))
+        .map(|path| {
+            Mapping::load(&path, &rules).unwrap_or_else(|err| {
+                eprintln!("couldn't load {}: {}", path, err);
+                exit(1);
+            })
+        });
+
+    if let Some(kind) = env::args().find_map(|a| a.strip_prefix("--play").map(String::from)) {
+        let guide = mapping.unwrap_or_else(|| Mapping::outcomes(&rules));
+        let rounds = read_lines("inputs/day2.txt", |line| guide.parse(&rules, line))
+            .expect("could not parse the data?");
+        let mut opponent: Box<dyn Opponent> = match kind.as_str() {
+            "" | "=guide" => Box::new(GuideOpponent::new(&rounds)),
+            "=predict" => Box::new(Predictor::new(&rules, &rounds)),
+            _ => {
+                eprintln!(
+                    "unknown opponent {:?}, try --play=guide or --play=predict",
+                    kind
+                );
+                exit(1);
+            }
+        };
+        let played = play(&rules, opponent.as_mut(), io::stdin().lock(), io::stdout())
+            .expect("couldn't talk to the terminal");
+        print!("{}", Report::new(&rules, &played));
+        return;
+    }
+
+    if env::args().any(|a| a == "--optimize") {
+        let guide = read_lines("inputs/day2.txt", Ok::<String, Infallible>)
+            .expect("could not read the guide");
+        let base = mapping.unwrap_or_else(|| Mapping::literal(&rules));
+        optimize(&base, &rules, &guide).expect("could not parse the data?");
+        return;
+    }
+
+    if let Some(mapping) = mapping {
+        let data = read_lines("inputs/day2.txt", |line| mapping.parse(&rules, line))
+            .expect("could not parse the data?");
+        let sum: u32 = data.iter().map(|r| r.score(&rules)).sum();
+        println!("Score: {}", sum);
+        if report {
+            print!("{}", Report::new(&rules, &data));
+        }
+        return;
+    }
+
+    let literal = Mapping::literal(&rules);
+    let data = read_lines("inputs/day2.txt", |line| literal.parse(&rules, line))
+        .expect("could not parse the data?");
+    for round in &data {
+        trace!(
+            "{} against {}: {}",
+            rules.name(round.player),
+            rules.name(round.opponent),
+            round.score(&rules)
+        );
+    }
+    let sum: u32 = data.iter().map(|r| r.score(&rules)).sum();
     println!("Final score: {}", sum);
-    println!("oops...");
-    let data =
-        read_lines("inputs/day2.txt", Round::parse_part2).expect("could not parse the data?");
-    let sum: u32 = data.iter().map(|r| r.score()).sum();
+    if report {
+        print!("{}", Report::new(&rules, &data));
+    }
+    info!("oops...");
+    let outcomes = Mapping::outcomes(&rules);
+    let data = read_lines("inputs/day2.txt", |line| outcomes.parse(&rules, line))
+        .expect("could not parse the data?");
+    let sum: u32 = data.iter().map(|r| r.score(&rules)).sum();
     println!("Correct score: {}", sum);
+    if report {
+        print!("{}", Report::new(&rules, &data));
+    }
 }
 
-#[derive(Debug, PartialEq, Clone, Copy)]
-enum Shape {
-    Rock,
-    Paper,
-    Scissors,
-}
+/// One of the shapes in a set of [`Rules`], by where it is in them.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+struct Shape(usize);
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
 enum Outcome {
     Win,
     Draw,
     Lose,
 }
 
-impl Shape {
-    fn value(&self) -> u32 {
+impl Outcome {
+    const ALL: [Outcome; 3] = [Outcome::Lose, Outcome::Draw, Outcome::Win];
+
+    fn name(&self) -> &'static str {
         match self {
-            Self::Rock => 1,
-            Self::Paper => 2,
-            Self::Scissors => 3,
+            Self::Win => "win",
+            Self::Draw => "draw",
+            Self::Lose => "lose",
         }
     }
+}
 
-    fn against(&self, other: &Self) -> Outcome {
-        match (self, other) {
-            (a, b) if a == b => Outcome::Draw,
-            (Self::Rock, Self::Scissors)
-            | (Self::Paper, Self::Rock)
-            | (Self::Scissors, Self::Paper) => Outcome::Win,
-            _ => Outcome::Lose,
+/// A game where each shape beats some of the others. The shapes, what
+/// they're worth, and which beats which are all data, so that games other
+/// than rock, paper, scissors can be played.
+#[derive(Debug, Clone, PartialEq)]
+struct Rules {
+    names: Vec<String>,
+    values: Vec<u32>,
+    /// `beats[a][b]` is whether shape `a` beats shape `b`.
+    beats: Vec<Vec<bool>>,
+    win: u32,
+    draw: u32,
+    lose: u32,
+}
+
+impl Rules {
+    /// A game where the shapes go around in a circle, and each one beats
+    /// the shapes an odd number of steps behind it. There has to be an odd
+    /// number of shapes, so that two different shapes never draw.
+    fn cyclic(shapes: &[(&str, u32)]) -> Self {
+        let n = shapes.len();
+        assert!(n % 2 == 1, "a cyclic game needs an odd number of shapes");
+        Self {
+            names: shapes.iter().map(|(name, _)| name.to_string()).collect(),
+            values: shapes.iter().map(|(_, value)| *value).collect(),
+            beats: (0..n)
+                .map(|a| (0..n).map(|b| (a + n - b) % n % 2 == 1).collect())
+                .collect(),
+            win: 6,
+            draw: 3,
+            lose: 0,
         }
     }
 
-    fn to(have_outcome: Outcome, against: &Self) -> Self {
-        match (have_outcome, against) {
-            (Outcome::Draw, shape) => *shape,
-            (Outcome::Win, Self::Paper) => Self::Scissors,
-            (Outcome::Win, Self::Rock) => Self::Paper,
-            (Outcome::Win, Self::Scissors) => Self::Rock,
-            (Outcome::Lose, Self::Paper) => Self::Rock,
-            (Outcome::Lose, Self::Rock) => Self::Scissors,
-            (Outcome::Lose, Self::Scissors) => Self::Paper,
+    fn classic() -> Self {
+        Self::cyclic(&[("rock", 1), ("paper", 2), ("scissors", 3)])
+    }
+
+    fn lizard_spock() -> Self {
+        Self::cyclic(&[
+            ("rock", 1),
+            ("paper", 2),
+            ("scissors", 3),
+            ("spock", 4),
+            ("lizard", 5),
+        ])
+    }
+
+    fn by_name(name: &str) -> Option<Self> {
+        match name {
+            "classic" => Some(Self::classic()),
+            "lizard-spock" => Some(Self::lizard_spock()),
+            _ => None,
         }
     }
-}
 
-impl Outcome {
-    fn value(&self) -> u32 {
-        match self {
-            Self::Win => 6,
-            Self::Draw => 3,
-            Self::Lose => 0,
+    fn shapes(&self) -> impl DoubleEndedIterator<Item = Shape> {
+        (0..self.names.len()).map(Shape)
+    }
+
+    fn shape(&self, name: &str) -> Option<Shape> {
+        self.names.iter().position(|n| n == name).map(Shape)
+    }
+
+    fn name(&self, shape: Shape) -> &str {
+        &self.names[shape.0]
+    }
+
+    fn value(&self, shape: Shape) -> u32 {
+        self.values[shape.0]
+    }
+
+    fn outcome_value(&self, outcome: Outcome) -> u32 {
+        match outcome {
+            Outcome::Win => self.win,
+            Outcome::Draw => self.draw,
+            Outcome::Lose => self.lose,
         }
     }
+
+    fn against(&self, shape: Shape, other: Shape) -> Outcome {
+        if self.beats[shape.0][other.0] {
+            Outcome::Win
+        } else if self.beats[other.0][shape.0] {
+            Outcome::Lose
+        } else {
+            Outcome::Draw
+        }
+    }
+
+    /// The shape to play to get the outcome. When more than one would do,
+    /// the one that comes first in the rules is picked.
+    fn to(&self, have_outcome: Outcome, against: Shape) -> Shape {
+        self.shapes()
+            .find(|s| self.against(*s, against) == have_outcome)
+            .expect("every outcome should be possible against every shape")
+    }
 }
 
 #[derive(Debug)]
@@ -77,63 +227,509 @@ struct Round {
     pub player: Shape,
 }
 
-static ROUND_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"^\s*([ABC])\s+([XYZ])\s*$").unwrap());
+static ROUND_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"^\s*(\S+)\s+(\S+)\s*$").unwrap());
 
-impl Round {
-    fn parse(str: String) -> Result<Self, ParseError> {
-        if let Some(captures) = ROUND_REGEX.captures(&str) {
-            let opponent = match &captures[1] {
-                "A" => Shape::Rock,
-                "B" => Shape::Paper,
-                "C" => Shape::Scissors,
-                _ => panic!("impossible capture?"),
+/// What a symbol in the player's column of the strategy guide asks for.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum Meaning {
+    Play(Shape),
+    Get(Outcome),
+}
+
+/// Which symbols in a strategy guide mean what. The opponent's column is
+/// always a shape, but the player's column can ask for a shape or for an
+/// outcome, symbol by symbol.
+///
+/// Mappings can be loaded from a small TOML-like file:
+///
+/// ```text
+/// [opponent]
+/// A = "rock"
+/// B = "paper"
+/// C = "scissors"
+///
+/// [player]
+/// X = "lose"
+/// Y = "draw"
+/// Z = "win"
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+struct Mapping {
+    opponent: HashMap<String, Shape>,
+    player: HashMap<String, Meaning>,
+}
+
+impl Mapping {
+    /// `A`, `B` and `C` are the opponent's rock, paper and scissors, and
+    /// `X`, `Y` and `Z` are the player's.
+    fn literal(rules: &Rules) -> Self {
+        Self::from_config(
+            "[opponent]\nA = rock\nB = paper\nC = scissors\n\
+             [player]\nX = rock\nY = paper\nZ = scissors\n",
+            rules,
+        )
+        .expect("the rules should have rock, paper and scissors")
+    }
+
+    /// Like [`Mapping::literal`], except `X`, `Y` and `Z` say whether to
+    /// lose, draw or win.
+    fn outcomes(rules: &Rules) -> Self {
+        Self::from_config(
+            "[opponent]\nA = rock\nB = paper\nC = scissors\n\
+             [player]\nX = lose\nY = draw\nZ = win\n",
+            rules,
+        )
+        .expect("the rules should have rock, paper and scissors")
+    }
+
+    /// Will panic on IO error.
+    fn load(path: &str, rules: &Rules) -> Result<Self, MappingError> {
+        let text = fs::read_to_string(path).expect("could not read the mapping");
+        Self::from_config(&text, rules)
+    }
+
+    fn from_config(text: &str, rules: &Rules) -> Result<Self, MappingError> {
+        let mut mapping = Mapping {
+            opponent: HashMap::new(),
+            player: HashMap::new(),
+        };
+        let mut section = None;
+        for (i, line) in text.lines().enumerate() {
+            let line_number = i + 1;
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                section = match name.trim() {
+                    "opponent" | "player" => Some(name.trim().to_string()),
+                    _ => return Err(MappingError::UnknownSection(line_number, name.to_string())),
+                };
+                continue;
+            }
+            let Some((symbol, value)) = line.split_once('=') else {
+                return Err(MappingError::BadLine(line_number, line.to_string()));
             };
-            let player = match &captures[2] {
-                "X" => Shape::Rock,
-                "Y" => Shape::Paper,
-                "Z" => Shape::Scissors,
-                _ => panic!("impossible capture?"),
+            let symbol = symbol.trim().trim_matches('"').to_string();
+            let value = value.trim().trim_matches('"');
+            let meaning = match Outcome::ALL.iter().find(|o| o.name() == value) {
+                Some(outcome) => Meaning::Get(*outcome),
+                None => match rules.shape(value) {
+                    Some(shape) => Meaning::Play(shape),
+                    None => {
+                        return Err(MappingError::UnknownMeaning(line_number, value.to_string()))
+                    }
+                },
             };
-            Ok(Round { opponent, player })
-        } else {
-            Err(ParseError::NotMatched(str))
+            match (section.as_deref(), meaning) {
+                (Some("opponent"), Meaning::Play(shape)) => {
+                    mapping.opponent.insert(symbol, shape);
+                }
+                (Some("opponent"), Meaning::Get(_)) => {
+                    return Err(MappingError::OpponentOutcome(
+                        line_number,
+                        value.to_string(),
+                    ))
+                }
+                (Some(_), meaning) => {
+                    mapping.player.insert(symbol, meaning);
+                }
+                (None, _) => return Err(MappingError::NoSection(line_number)),
+            }
         }
+        Ok(mapping)
     }
 
-    fn parse_part2(str: String) -> Result<Self, ParseError> {
-        if let Some(captures) = ROUND_REGEX.captures(&str) {
-            let opponent = match &captures[1] {
-                "A" => Shape::Rock,
-                "B" => Shape::Paper,
-                "C" => Shape::Scissors,
-                _ => panic!("impossible capture?"),
-            };
-            let player = match &captures[2] {
-                "X" => Shape::to(Outcome::Lose, &opponent),
-                "Y" => Shape::to(Outcome::Draw, &opponent),
-                "Z" => Shape::to(Outcome::Win, &opponent),
-                _ => panic!("impossible capture?"),
-            };
-            Ok(Round { opponent, player })
-        } else {
-            Err(ParseError::NotMatched(str))
+    fn parse(&self, rules: &Rules, str: String) -> Result<Round, ParseError> {
+        let Some(captures) = ROUND_REGEX.captures(&str) else {
+            return Err(ParseError::NotMatched(str));
+        };
+        let opponent = *self
+            .opponent
+            .get(&captures[1])
+            .ok_or_else(|| ParseError::UnknownSymbol("opponent", captures[1].to_string()))?;
+        let player = match self.player.get(&captures[2]) {
+            Some(Meaning::Play(shape)) => *shape,
+            Some(Meaning::Get(outcome)) => rules.to(*outcome, opponent),
+            None => return Err(ParseError::UnknownSymbol("player", captures[2].to_string())),
+        };
+        Ok(Round { opponent, player })
+    }
+}
+
+impl Meaning {
+    fn name<'a>(&self, rules: &'a Rules) -> &'a str {
+        match self {
+            Self::Play(shape) => rules.name(*shape),
+            Self::Get(outcome) => outcome.name(),
+        }
+    }
+}
+
+/// The total score for following a strategy guide, read with `mapping`.
+fn score_guide(mapping: &Mapping, rules: &Rules, guide: &[String]) -> Result<u32, ParseError> {
+    let mut total = 0;
+    for line in guide {
+        total += mapping.parse(rules, line.clone())?.score(rules);
+    }
+    Ok(total)
+}
+
+/// Every way of giving each symbol a different one of the meanings.
+fn assignments(symbols: &[String], meanings: &[Meaning]) -> Vec<HashMap<String, Meaning>> {
+    let Some((symbol, rest)) = symbols.split_first() else {
+        return vec![HashMap::new()];
+    };
+    let mut found = vec![];
+    for (i, meaning) in meanings.iter().enumerate() {
+        let others: Vec<Meaning> = meanings
+            .iter()
+            .enumerate()
+            .filter(|(j, _)| *j != i)
+            .map(|(_, m)| *m)
+            .collect();
+        for mut assignment in assignments(rest, &others) {
+            assignment.insert(symbol.clone(), *meaning);
+            found.push(assignment);
+        }
+    }
+    found
+}
+
+/// Tries every way of reading the player's column as the given meanings,
+/// keeping the opponent's column as `base` reads it, and gives back the
+/// best one with its score. Ties go to whichever was tried first.
+fn best_mapping(
+    base: &Mapping,
+    rules: &Rules,
+    guide: &[String],
+    meanings: &[Meaning],
+) -> Result<Option<(Mapping, u32)>, ParseError> {
+    let mut symbols: Vec<String> = vec![];
+    for line in guide {
+        match ROUND_REGEX.captures(line) {
+            Some(captures) => symbols.push(captures[2].to_string()),
+            None => return Err(ParseError::NotMatched(line.clone())),
+        }
+    }
+    symbols.sort();
+    symbols.dedup();
+
+    let mut best: Option<(Mapping, u32)> = None;
+    for player in assignments(&symbols, meanings) {
+        let mapping = Mapping {
+            opponent: base.opponent.clone(),
+            player,
+        };
+        let score = score_guide(&mapping, rules, guide)?;
+        if best.as_ref().map_or(true, |(_, best)| score > *best) {
+            best = Some((mapping, score));
+        }
+    }
+    Ok(best)
+}
+
+/// Lists what each of the player's symbols means, like `X = rock, Y = win`.
+fn describe_player(mapping: &Mapping, rules: &Rules) -> String {
+    let mut symbols: Vec<(&String, &Meaning)> = mapping.player.iter().collect();
+    symbols.sort_by_key(|(symbol, _)| *symbol);
+    symbols
+        .iter()
+        .map(|(symbol, meaning)| format!("{} = {}", symbol, meaning.name(rules)))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Reports how the guide scores when read with `base`, and then the best
+/// readings of the player's column, with the opponent's still read by `base`.
+fn optimize(base: &Mapping, rules: &Rules, guide: &[String]) -> Result<(), ParseError> {
+    println!(
+        "the guide scores {} with {}",
+        score_guide(base, rules, guide)?,
+        describe_player(base, rules)
+    );
+    let shapes: Vec<Meaning> = rules.shapes().map(Meaning::Play).collect();
+    let outcomes: Vec<Meaning> = Outcome::ALL.into_iter().map(Meaning::Get).collect();
+    for (kind, meanings) in [("shapes", shapes), ("outcomes", outcomes)] {
+        match best_mapping(base, rules, guide, &meanings)? {
+            Some((mapping, score)) => println!(
+                "the best reading as {} scores {}, with {}",
+                kind,
+                score,
+                describe_player(&mapping, rules)
+            ),
+            None => println!("there aren't enough {} to go round", kind),
         }
     }
+    Ok(())
+}
+
+impl Round {
+    fn outcome(&self, rules: &Rules) -> Outcome {
+        rules.against(self.player, self.opponent)
+    }
+
+    fn score(&self, rules: &Rules) -> u32 {
+        rules.value(self.player) + rules.outcome_value(self.outcome(rules))
+    }
+}
+
+/// How a whole tournament of rounds went, from the player's side.
+#[derive(Debug, PartialEq)]
+struct Report {
+    total: u32,
+    wins: usize,
+    draws: usize,
+    losses: usize,
+    /// How many rounds got each score.
+    scores: BTreeMap<u32, usize>,
+    /// The most wins in a row.
+    longest_streak: usize,
+    /// How often the player used each shape, in the order of the rules.
+    usage: Vec<(String, usize)>,
+}
+
+impl Report {
+    fn new(rules: &Rules, rounds: &[Round]) -> Self {
+        let mut report = Report {
+            total: 0,
+            wins: 0,
+            draws: 0,
+            losses: 0,
+            scores: BTreeMap::new(),
+            longest_streak: 0,
+            usage: rules
+                .shapes()
+                .map(|s| (rules.name(s).to_string(), 0))
+                .collect(),
+        };
+        let mut streak = 0;
+        for round in rounds {
+            let score = round.score(rules);
+            report.total += score;
+            *report.scores.entry(score).or_default() += 1;
+            report.usage[round.player.0].1 += 1;
+            match round.outcome(rules) {
+                Outcome::Win => {
+                    report.wins += 1;
+                    streak += 1;
+                    report.longest_streak = report.longest_streak.max(streak);
+                }
+                Outcome::Draw => {
+                    report.draws += 1;
+                    streak = 0;
+                }
+                Outcome::Lose => {
+                    report.losses += 1;
+                    streak = 0;
+                }
+            }
+        }
+        report
+    }
+}
 
-    fn score(&self) -> u32 {
-        self.player.value() + self.player.against(&self.opponent).value()
+impl Display for Report {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let rounds = self.wins + self.draws + self.losses;
+        writeln!(f, "{} rounds, scoring {}", rounds, self.total)?;
+        writeln!(
+            f,
+            "won {}, drew {}, lost {}; at most {} wins in a row",
+            self.wins, self.draws, self.losses, self.longest_streak
+        )?;
+        let scores: Vec<String> = self
+            .scores
+            .iter()
+            .map(|(score, count)| format!("{}: {}", score, count))
+            .collect();
+        writeln!(f, "rounds by score: {}", scores.join(", "))?;
+        let usage: Vec<String> = self
+            .usage
+            .iter()
+            .map(|(name, count)| format!("{} {}", name, count))
+            .collect();
+        writeln!(f, "played: {}", usage.join(", "))
     }
 }
 
+/// Someone to play against.
+trait Opponent {
+    fn name(&self) -> String;
+    fn next(&mut self, rules: &Rules) -> Shape;
+    /// Lets the opponent see what the player just did.
+    fn observe(&mut self, _player: Shape) {}
+}
+
+/// Plays the opponent's side of a strategy guide, over and over.
+struct GuideOpponent {
+    moves: Vec<Shape>,
+    next: usize,
+}
+
+impl GuideOpponent {
+    fn new(rounds: &[Round]) -> Self {
+        Self {
+            moves: rounds.iter().map(|r| r.opponent).collect(),
+            next: 0,
+        }
+    }
+}
+
+impl Opponent for GuideOpponent {
+    fn name(&self) -> String {
+        "the strategy guide".to_string()
+    }
+
+    fn next(&mut self, _rules: &Rules) -> Shape {
+        if self.moves.is_empty() {
+            return Shape(0);
+        }
+        let shape = self.moves[self.next % self.moves.len()];
+        self.next += 1;
+        shape
+    }
+}
+
+/// Guesses that the player will pick whatever they've picked most, starting
+/// from the player's side of a strategy guide, and plays to beat it.
+struct Predictor {
+    counts: Vec<usize>,
+}
+
+impl Predictor {
+    fn new(rules: &Rules, rounds: &[Round]) -> Self {
+        let mut counts = vec![0; rules.names.len()];
+        for round in rounds {
+            counts[round.player.0] += 1;
+        }
+        Self { counts }
+    }
+}
+
+impl Opponent for Predictor {
+    fn name(&self) -> String {
+        "the predictor".to_string()
+    }
+
+    fn next(&mut self, rules: &Rules) -> Shape {
+        let likely = rules
+            .shapes()
+            .rev()
+            .max_by_key(|s| self.counts[s.0])
+            .expect("the rules should have some shapes");
+        rules.to(Outcome::Win, likely)
+    }
+
+    fn observe(&mut self, player: Shape) {
+        self.counts[player.0] += 1;
+    }
+}
+
+/// The shape whose name starts with `typed`, as long as only one does.
+fn shape_from_prefix(rules: &Rules, typed: &str) -> Option<Shape> {
+    if typed.is_empty() {
+        return None;
+    }
+    let mut matches = rules.shapes().filter(|s| rules.name(*s).starts_with(typed));
+    let shape = matches.next()?;
+    matches.next().is_none().then_some(shape)
+}
+
+/// Plays rounds against `opponent`, reading the player's shapes a line at a
+/// time until `quit` or the end of the input, and gives back the rounds that
+/// were played.
+fn play<R: BufRead, W: Write>(
+    rules: &Rules,
+    opponent: &mut dyn Opponent,
+    input: R,
+    mut out: W,
+) -> io::Result<Vec<Round>> {
+    let names = rules.names.join(", ");
+    writeln!(
+        out,
+        "playing against {}; pick one of {}, or quit",
+        opponent.name(),
+        names
+    )?;
+    let mut rounds = vec![];
+    let mut total = 0;
+    let mut lines = input.lines();
+    loop {
+        write!(out, "> ")?;
+        out.flush()?;
+        let Some(line) = lines.next() else {
+            writeln!(out)?;
+            break;
+        };
+        let typed = line?.trim().to_lowercase();
+        if typed == "quit" || typed == "q" {
+            break;
+        }
+        let Some(player) = shape_from_prefix(rules, &typed) else {
+            writeln!(out, "{:?} isn't one of {}", typed, names)?;
+            continue;
+        };
+        let round = Round {
+            opponent: opponent.next(rules),
+            player,
+        };
+        opponent.observe(player);
+        let score = round.score(rules);
+        total += score;
+        writeln!(
+            out,
+            "{} against {}: {}, {} points ({} so far)",
+            rules.name(round.player),
+            rules.name(round.opponent),
+            round.outcome(rules).name(),
+            score,
+            total
+        )?;
+        rounds.push(round);
+    }
+    Ok(rounds)
+}
+
 #[derive(Error, Debug)]
 enum ParseError {
     #[error("line did not match '{0}'")]
     NotMatched(String),
+    #[error("{1:?} doesn't mean anything in the {0} column")]
+    UnknownSymbol(&'static str, String),
+}
+
+#[derive(Error, Debug, PartialEq)]
+enum MappingError {
+    #[error("line {0}: expected a section or `symbol = meaning`, got {1:?}")]
+    BadLine(usize, String),
+    #[error("line {0}: there is no [{1}] section, only [opponent] and [player]")]
+    UnknownSection(usize, String),
+    #[error("line {0}: {1:?} isn't a shape in these rules, or an outcome")]
+    UnknownMeaning(usize, String),
+    #[error("line {0}: the opponent can only play shapes, not {1:?}")]
+    OpponentOutcome(usize, String),
+    #[error("line {0}: symbols need to be in an [opponent] or [player] section")]
+    NoSection(usize),
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::ROUND_REGEX;
+    use crate::{
+        assignments, best_mapping, describe_player, play, score_guide, shape_from_prefix,
+        GuideOpponent, Mapping, MappingError, Meaning, Opponent, Outcome, Predictor, Report, Round,
+        Rules, ROUND_REGEX,
+    };
+
+    const EXAMPLE: [&str; 3] = ["A Y", "B X", "C Z"];
+
+    /// Reads every line of a guide with `mapping`.
+    fn rounds(mapping: &Mapping, rules: &Rules, lines: &[&str]) -> Vec<Round> {
+        lines
+            .iter()
+            .map(|l| mapping.parse(rules, l.to_string()).unwrap())
+            .collect()
+    }
 
     #[test]
     fn parsing_regex() {
@@ -144,7 +740,12 @@ mod tests {
             ROUND_REGEX.is_match("       A                          Y      \t "),
             "spaces are ignored"
         );
-        assert!(!ROUND_REGEX.is_match("AA Y"), "repetitions not allowed");
+        assert!(
+            Mapping::literal(&Rules::classic())
+                .parse(&Rules::classic(), "AA Y".to_string())
+                .is_err(),
+            "repetitions not allowed"
+        );
 
         let captures = ROUND_REGEX.captures("      A    X").unwrap();
         assert_eq!(&captures[1], "A", "first capture is A");
@@ -153,4 +754,223 @@ mod tests {
         let captures = ROUND_REGEX.captures("not gonna happen");
         assert!(captures.is_none(), "should give None if we don't match");
     }
+
+    #[test]
+    fn classic_rules() {
+        let rules = Rules::classic();
+        let shape = |name| rules.shape(name).unwrap();
+        let (rock, paper, scissors) = (shape("rock"), shape("paper"), shape("scissors"));
+        assert_eq!(rules.against(paper, rock), Outcome::Win);
+        assert_eq!(rules.against(rock, paper), Outcome::Lose);
+        assert_eq!(rules.against(rock, scissors), Outcome::Win);
+        assert_eq!(rules.against(scissors, scissors), Outcome::Draw);
+        assert_eq!(rules.to(Outcome::Win, scissors), rock);
+        assert_eq!(rules.to(Outcome::Lose, rock), scissors);
+        assert_eq!(rules.to(Outcome::Draw, paper), paper);
+        assert_eq!(rules.name(paper), "paper");
+    }
+
+    #[test]
+    fn lizard_spock_rules() {
+        let rules = Rules::lizard_spock();
+        let shape = |name| rules.shape(name).unwrap();
+        let beats = [
+            ("scissors", "paper"),
+            ("paper", "rock"),
+            ("rock", "lizard"),
+            ("lizard", "spock"),
+            ("spock", "scissors"),
+            ("scissors", "lizard"),
+            ("lizard", "paper"),
+            ("paper", "spock"),
+            ("spock", "rock"),
+            ("rock", "scissors"),
+        ];
+        for (winner, loser) in beats {
+            assert_eq!(rules.against(shape(winner), shape(loser)), Outcome::Win);
+            assert_eq!(rules.against(shape(loser), shape(winner)), Outcome::Lose);
+        }
+        for a in rules.shapes() {
+            for outcome in [Outcome::Win, Outcome::Draw, Outcome::Lose] {
+                assert_eq!(rules.against(rules.to(outcome, a), a), outcome);
+            }
+        }
+    }
+
+    #[test]
+    fn scoring_the_example() {
+        let rules = Rules::classic();
+        let score = |mapping: &Mapping| -> u32 {
+            rounds(mapping, &rules, &EXAMPLE)
+                .iter()
+                .map(|r| r.score(&rules))
+                .sum()
+        };
+        assert_eq!(score(&Mapping::literal(&rules)), 15);
+        assert_eq!(score(&Mapping::outcomes(&rules)), 12);
+    }
+
+    #[test]
+    fn optimizing_the_example() {
+        let rules = Rules::classic();
+        let guide: Vec<String> = EXAMPLE.map(String::from).to_vec();
+        let literal = Mapping::literal(&rules);
+        assert_eq!(score_guide(&literal, &rules, &guide).unwrap(), 15);
+
+        let shapes: Vec<Meaning> = rules.shapes().map(Meaning::Play).collect();
+        let (mapping, score) = best_mapping(&literal, &rules, &guide, &shapes)
+            .unwrap()
+            .unwrap();
+        assert_eq!(score, 24);
+        assert_eq!(
+            describe_player(&mapping, &rules),
+            "X = scissors, Y = paper, Z = rock"
+        );
+
+        let outcomes: Vec<Meaning> = Outcome::ALL.into_iter().map(Meaning::Get).collect();
+        let (mapping, score) = best_mapping(&literal, &rules, &guide, &outcomes)
+            .unwrap()
+            .unwrap();
+        assert_eq!(score, 18);
+        assert_eq!(
+            describe_player(&mapping, &rules),
+            "X = win, Y = lose, Z = draw"
+        );
+    }
+
+    #[test]
+    fn reporting_on_the_example() {
+        let rules = Rules::classic();
+        let report = Report::new(&rules, &rounds(&Mapping::literal(&rules), &rules, &EXAMPLE));
+        assert_eq!((report.wins, report.draws, report.losses), (1, 1, 1));
+        assert_eq!(report.total, 15);
+        assert_eq!(
+            report.scores.into_iter().collect::<Vec<_>>(),
+            [(1, 1), (6, 1), (8, 1)]
+        );
+
+        let report = Report::new(
+            &rules,
+            &rounds(&Mapping::outcomes(&rules), &rules, &EXAMPLE),
+        );
+        assert_eq!(
+            report.to_string(),
+            concat!(
+                "3 rounds, scoring 12\n",
+                "won 1, drew 1, lost 1; at most 1 wins in a row\n",
+                "rounds by score: 1: 1, 4: 1, 7: 1\n",
+                "played: rock 3, paper 0, scissors 0\n",
+            )
+        );
+    }
+
+    #[test]
+    fn winning_streaks() {
+        let rules = Rules::classic();
+        let lines = ["A Z", "B Z", "C Y", "A Z", "B Z", "C Z", "A X"];
+        let report = Report::new(&rules, &rounds(&Mapping::outcomes(&rules), &rules, &lines));
+        assert_eq!(report.longest_streak, 3);
+        assert_eq!((report.wins, report.draws, report.losses), (5, 1, 1));
+        assert_eq!(report.usage.iter().map(|(_, n)| n).sum::<usize>(), 7);
+    }
+
+    #[test]
+    fn playing_against_the_guide() {
+        let rules = Rules::classic();
+        let mut opponent =
+            GuideOpponent::new(&rounds(&Mapping::outcomes(&rules), &rules, &EXAMPLE));
+        let input = "paper\nspock\ns\nquit\nrock\n".as_bytes();
+        let mut out = vec![];
+        let rounds = play(&rules, &mut opponent, input, &mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert_eq!(rounds.len(), 2);
+        assert!(out.contains("paper against rock: win, 8 points (8 so far)"));
+        assert!(out.contains("\"spock\" isn't one of rock, paper, scissors"));
+        assert!(out.contains("scissors against paper: win, 9 points (17 so far)"));
+        assert_eq!(Report::new(&rules, &rounds).total, 17);
+
+        // it goes back to the start of the guide when it runs out.
+        let moves: Vec<_> = (0..4).map(|_| opponent.next(&rules)).collect();
+        let names: Vec<&str> = moves.iter().map(|s| rules.name(*s)).collect();
+        assert_eq!(names, ["scissors", "rock", "paper", "scissors"]);
+    }
+
+    #[test]
+    fn predicting_the_player() {
+        let rules = Rules::classic();
+        let shape = |name| rules.shape(name).unwrap();
+        // the example guide has the player using rock every time.
+        let mut predictor = Predictor::new(
+            &rules,
+            &rounds(&Mapping::outcomes(&rules), &rules, &EXAMPLE),
+        );
+        assert_eq!(predictor.next(&rules), shape("paper"));
+        for _ in 0..4 {
+            predictor.observe(shape("scissors"));
+        }
+        assert_eq!(predictor.next(&rules), shape("rock"));
+
+        let spock = Rules::lizard_spock();
+        assert_eq!(shape_from_prefix(&spock, "l"), spock.shape("lizard"));
+        assert_eq!(shape_from_prefix(&spock, "s"), None);
+        assert_eq!(shape_from_prefix(&spock, "sp"), spock.shape("spock"));
+        assert_eq!(shape_from_prefix(&spock, ""), None);
+    }
+
+    #[test]
+    fn every_assignment() {
+        let symbols: Vec<String> = ["X", "Y", "Z"].map(String::from).to_vec();
+        let outcomes: Vec<Meaning> = Outcome::ALL.into_iter().map(Meaning::Get).collect();
+        assert_eq!(assignments(&symbols, &outcomes).len(), 6);
+        let spock = Rules::lizard_spock();
+        let shapes: Vec<Meaning> = spock.shapes().map(Meaning::Play).collect();
+        assert_eq!(assignments(&symbols, &shapes).len(), 60);
+        assert!(assignments(&symbols, &outcomes[..2]).is_empty());
+    }
+
+    #[test]
+    fn loading_mappings() {
+        let rules = Rules::classic();
+        let config = concat!(
+            "# the opponent's column\n",
+            "[opponent]\n",
+            "A = \"rock\"\n",
+            "B = \"paper\"\n",
+            "C = \"scissors\"\n",
+            "\n",
+            "[player]\n",
+            "X = \"lose\"\n",
+            "Y = \"draw\"\n",
+            "Z = \"win\"\n",
+        );
+        let mapping = Mapping::from_config(config, &rules).unwrap();
+        assert_eq!(mapping, Mapping::outcomes(&rules));
+
+        // the columns can be read some other way entirely.
+        let mixed = "[opponent]\n1 = scissors\n2 = rock\n[player]\nR = rock\nW = win\n";
+        let mapping = Mapping::from_config(mixed, &rules).unwrap();
+        let round = mapping.parse(&rules, "1 W".to_string()).unwrap();
+        assert_eq!(round.player, rules.shape("rock").unwrap());
+        assert_eq!(round.score(&rules), 7);
+        assert!(mapping.parse(&rules, "3 W".to_string()).is_err());
+
+        let error = |config| Mapping::from_config(config, &rules).unwrap_err();
+        assert_eq!(error("A = rock"), MappingError::NoSection(1));
+        assert_eq!(
+            error("[opponent]\nA = win"),
+            MappingError::OpponentOutcome(2, "win".to_string())
+        );
+        assert_eq!(
+            error("[player]\nX = spock"),
+            MappingError::UnknownMeaning(2, "spock".to_string())
+        );
+        assert_eq!(
+            error("[me]"),
+            MappingError::UnknownSection(1, "me".to_string())
+        );
+        assert_eq!(
+            error("[player]\nX rock"),
+            MappingError::BadLine(2, "X rock".to_string())
+        );
+    }
 }
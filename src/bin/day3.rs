@@ -1,6 +1,6 @@
-use std::{collections::HashSet, f32::consts::E, hash::Hash};
+use std::{collections::HashSet, env};
 
-use aoc::data::read_lines;
+use aoc::{data::read_lines, info, log};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -10,40 +10,47 @@ enum Day3Error {
         #[from]
         err: ParseError,
     },
+    #[error("couldn't group the rucksacks: {err}")]
+    GroupError {
+        #[from]
+        err: GroupError,
+    },
 }
 
 fn main() -> Result<(), Day3Error> {
-    let data = read_lines("inputs/day3.txt", |l| parse_line(l))?;
+    log::init();
+    let data = read_lines("inputs/day3.txt", parse_line)?;
     let total: u32 = data
         .iter()
         .map(|r| r.common_types().iter().map(assign_value).sum::<u32>())
         .sum();
     println!("part1 {}", total);
 
-    let mut it = data.iter();
-
-    let mut total2: u32 = 0;
-
-    loop {
-        let Some(a) = it.next() else {
-            break;
-        };
-        let b = it
-            .next()
-            .expect("expected data's length to be multiple of 3");
-        let c = it
-            .next()
-            .expect("expected data's length to be multiple of 3");
-
-        a.all()
-            .intersection(&b.all())
-            .copied()
-            .collect::<HashSet<u8>>()
-            .intersection(&c.all())
-            .copied()
-            .for_each(|v| total2 += assign_value(&v))
+    let size = env::args()
+        .find_map(|a| a.strip_prefix("--group=").map(String::from))
+        .map_or(3, |n| n.parse().expect("--group should be given a number"));
+    if let Some(spec) = env::args().find_map(|a| a.strip_prefix("--groups=").map(String::from)) {
+        // groups of rucksack numbers, like 0,1,2;3,4,5
+        let groups: Vec<Vec<usize>> = spec
+            .split(';')
+            .map(|group| {
+                group
+                    .split(',')
+                    .filter(|n| !n.trim().is_empty())
+                    .map(|n| n.trim().parse().expect("--groups should be given numbers"))
+                    .collect()
+            })
+            .collect();
+        println!("part2 {}", group_priorities(by_indices(&data, &groups)?));
+        return Ok(());
     }
-    println!("part2 {}", total2);
+    let groups = if env::args().any(|a| a == "--sliding") {
+        info!("looking at every {} rucksacks in a row", size);
+        sliding(&data, size)?
+    } else {
+        chunked(&data, size)?
+    };
+    println!("part2 {}", group_priorities(groups));
     Ok(())
 }
 
@@ -66,11 +73,7 @@ struct Rucksack {
 
 impl Rucksack {
     pub fn common_types(&self) -> Vec<u8> {
-        self.left
-            .intersection(&self.right)
-            .into_iter()
-            .map(|v| *v)
-            .collect()
+        self.left.intersection(&self.right).copied().collect()
     }
 
     pub fn all(&self) -> HashSet<u8> {
@@ -78,6 +81,103 @@ impl Rucksack {
     }
 }
 
+#[derive(Error, Debug, PartialEq)]
+enum GroupError {
+    #[error("a group needs at least one rucksack")]
+    Empty,
+    #[error("{count} rucksacks don't split into groups of {size}, {left_over} would be left over")]
+    Incomplete {
+        size: usize,
+        count: usize,
+        left_over: usize,
+    },
+    #[error("a group of {size} needs at least {size} rucksacks, there are only {count}")]
+    TooFew { size: usize, count: usize },
+    #[error("there's no rucksack {index}, there are only {count}")]
+    OutOfRange { index: usize, count: usize },
+}
+
+/// The item types that every rucksack in the group has.
+fn common_items<'a, I>(group: I) -> HashSet<u8>
+where
+    I: IntoIterator<Item = &'a Rucksack>,
+{
+    let mut rucksacks = group.into_iter();
+    let Some(first) = rucksacks.next() else {
+        return HashSet::new();
+    };
+    rucksacks.fold(first.all(), |common, r| {
+        common.intersection(&r.all()).copied().collect()
+    })
+}
+
+/// Splits the rucksacks up into groups of `size`, in order. Every rucksack
+/// has to end up in a group.
+fn chunked(rucksacks: &[Rucksack], size: usize) -> Result<Vec<&[Rucksack]>, GroupError> {
+    if size == 0 {
+        return Err(GroupError::Empty);
+    }
+    let left_over = rucksacks.len() % size;
+    if left_over != 0 {
+        return Err(GroupError::Incomplete {
+            size,
+            count: rucksacks.len(),
+            left_over,
+        });
+    }
+    Ok(rucksacks.chunks(size).collect())
+}
+
+/// Every run of `size` rucksacks in a row, overlapping each other.
+fn sliding(rucksacks: &[Rucksack], size: usize) -> Result<Vec<&[Rucksack]>, GroupError> {
+    if size == 0 {
+        return Err(GroupError::Empty);
+    }
+    if rucksacks.len() < size {
+        return Err(GroupError::TooFew {
+            size,
+            count: rucksacks.len(),
+        });
+    }
+    Ok(rucksacks.windows(size).collect())
+}
+
+/// Groups picked out by hand, as lists of which rucksacks are in them.
+fn by_indices<'a>(
+    rucksacks: &'a [Rucksack],
+    groups: &[Vec<usize>],
+) -> Result<Vec<Vec<&'a Rucksack>>, GroupError> {
+    groups
+        .iter()
+        .map(|group| {
+            if group.is_empty() {
+                return Err(GroupError::Empty);
+            }
+            group
+                .iter()
+                .map(|&index| {
+                    rucksacks.get(index).ok_or(GroupError::OutOfRange {
+                        index,
+                        count: rucksacks.len(),
+                    })
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// The sum of the priorities of the items that each group has in common.
+fn group_priorities<'a, G, I>(groups: G) -> u32
+where
+    G: IntoIterator<Item = I>,
+    I: IntoIterator<Item = &'a Rucksack>,
+{
+    groups
+        .into_iter()
+        .map(|group| common_items(group).iter().map(assign_value).sum::<u32>())
+        .sum()
+}
+
 #[derive(Error, Debug)]
 enum ParseError {
     #[error("the rucksack doesn't have an even length {0}")]
@@ -97,3 +197,81 @@ fn parse_line(l: String) -> Result<Rucksack, ParseError> {
     }
     Ok(Rucksack { left, right })
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        by_indices, chunked, common_items, group_priorities, parse_line, sliding, GroupError,
+        Rucksack,
+    };
+
+    fn example() -> Vec<Rucksack> {
+        [
+            "vJrwpWtwJgWrhcsFMMfFFhFp",
+            "jqHRNqRjqzjGDLGLrsFMfFZSrLrFZsSL",
+            "PmmdzqPrVvPwwTWBwg",
+            "wMqvLMZHhHMvwLHjbvcjnnSBnvTQFn",
+            "ttgJtRGJQctTZtZT",
+            "CrZsJsPPZsGzwwsLwLmpwMDw",
+        ]
+        .iter()
+        .map(|l| parse_line(l.to_string()).unwrap())
+        .collect()
+    }
+
+    #[test]
+    fn groups_of_three() {
+        let rucksacks = example();
+        let groups = chunked(&rucksacks, 3).unwrap();
+        assert_eq!(
+            common_items(groups[0]).into_iter().collect::<Vec<_>>(),
+            [b'r']
+        );
+        assert_eq!(
+            common_items(groups[1]).into_iter().collect::<Vec<_>>(),
+            [b'Z']
+        );
+        assert_eq!(group_priorities(groups), 70);
+    }
+
+    #[test]
+    fn other_groupings() {
+        let rucksacks = example();
+        assert_eq!(
+            chunked(&rucksacks, 4).unwrap_err(),
+            GroupError::Incomplete {
+                size: 4,
+                count: 6,
+                left_over: 2
+            }
+        );
+        assert_eq!(chunked(&rucksacks, 0).unwrap_err(), GroupError::Empty);
+        assert_eq!(chunked(&rucksacks, 2).unwrap().len(), 3);
+
+        assert_eq!(sliding(&rucksacks, 3).unwrap().len(), 4);
+        assert_eq!(
+            sliding(&rucksacks, 7).unwrap_err(),
+            GroupError::TooFew { size: 7, count: 6 }
+        );
+        // a group of one has every item it carries in common with itself.
+        assert_eq!(
+            group_priorities(sliding(&rucksacks[..1], 1).unwrap()),
+            rucksacks[0]
+                .all()
+                .iter()
+                .map(crate::assign_value)
+                .sum::<u32>()
+        );
+
+        let custom = by_indices(&rucksacks, &[vec![0, 1, 2], vec![3, 4, 5]]).unwrap();
+        assert_eq!(group_priorities(custom), 70);
+        assert_eq!(
+            by_indices(&rucksacks, &[vec![0, 6]]).unwrap_err(),
+            GroupError::OutOfRange { index: 6, count: 6 }
+        );
+        assert_eq!(
+            by_indices(&rucksacks, &[vec![]]).unwrap_err(),
+            GroupError::Empty
+        );
+    }
+}
@@ -1,16 +1,17 @@
-use aoc::data::read_lines;
+use aoc::{data::read_lines, info, log, trace};
 use nom::sequence::separated_pair;
 use thiserror::Error;
 
 fn main() {
-    println!("day 4");
+    log::init();
+    info!("day 4");
     let data = read_lines("inputs/day4.txt", |l| {
         let wat = parse_assignment_pair(&l);
         let ok = wat.map(|(_, res)| res);
         ok.map_err(|_| ParseError::Bad("bleh".to_string()))
     })
     .expect("something didn't parse");
-    println!("first? {:?}", data[0]);
+    trace!("first? {:?}", data[0]);
 
     let contained = data
         .iter()
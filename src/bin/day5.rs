@@ -1,18 +1,23 @@
 use std::{
-    collections::HashSet,
+    collections::{HashSet, VecDeque},
+    env,
+    fmt::{self, Display},
     fs::File,
     io::{BufReader, Read},
+    process::exit,
 };
 
+use aoc::{log, trace};
 use nom::{
     bytes::complete::tag,
-    character::complete::{line_ending, newline, satisfy, space0, space1},
+    character::complete::{digit1, line_ending, satisfy, space0, space1},
     multi::{many1, separated_list1},
     sequence::{delimited, terminated},
-    ToUsize,
 };
+use thiserror::Error;
 
 fn main() {
+    log::init();
     let f = File::open("inputs/day5.txt").expect("File should exist");
     let mut buf_read = BufReader::new(f);
     let mut text = String::new();
@@ -20,26 +25,501 @@ fn main() {
         .read_to_string(&mut text)
         .expect("failed to read file");
 
-    let (rest, (crate_def, crate_indexes, moves)) = parse_file(&text).expect("didn't parse ok");
-    println!("received?\n{:?}", crate_def);
-    let mut stack = CrateStacks::construct(&crate_def).expect("it should work!");
-    let mut stack2 = CrateStacks::construct(&crate_def).expect("it should work");
-    println!("got out?\n{:?}", stack);
+    let (_, ((crate_def, _), moves)) = parse_file(&text).expect("didn't parse ok");
+    trace!("received?\n{:?}", crate_def);
+    trace!(
+        "got out?\n{}",
+        CrateStacks::construct(&crate_def)
+            .expect("it should work!")
+            .to_string()
+            .trim_end()
+    );
+
+    let args: Vec<String> = env::args().skip(1).collect();
+    let lenient = args.iter().any(|a| a == "--lenient");
+    let mut crane_names: Vec<&str> = args
+        .iter()
+        .filter_map(|a| a.strip_prefix("--crane="))
+        .collect();
+    if crane_names.is_empty() {
+        crane_names = vec!["9000", "9001"];
+    }
+    let trace = TraceMode::from_args(args.iter().cloned());
+    let solve_for = args.iter().find_map(|a| a.strip_prefix("--solve="));
+    let max_moves = args
+        .iter()
+        .find_map(|a| a.strip_prefix("--max-moves="))
+        .map(|n| n.parse().expect("--max-moves should be a number"))
+        .unwrap_or(2);
+    let after: Vec<usize> = args
+        .iter()
+        .filter_map(|a| a.strip_prefix("--after="))
+        .map(|k| k.parse().expect("--after should be a number"))
+        .collect();
+    let undo: usize = args
+        .iter()
+        .find_map(|a| a.strip_prefix("--undo="))
+        .map_or(0, |n| n.parse().expect("--undo should be a number"));
+    let redo: usize = args
+        .iter()
+        .find_map(|a| a.strip_prefix("--redo="))
+        .map_or(0, |n| n.parse().expect("--redo should be a number"));
+    if redo > 0 && undo == 0 {
+        eprintln!("--redo only makes sense after --undo");
+        exit(1);
+    }
+
+    for name in crane_names {
+        let Some(crane) = crane_by_name(name) else {
+            eprintln!("Unknown crane: {}", name);
+            exit(1);
+        };
+        let mut stacks = CrateStacks::construct(&crate_def).expect("it should work!");
+        if let Some(target) = solve_for {
+            match solve_for_message(&stacks, target, crane.as_ref(), max_moves) {
+                Some(solution) => {
+                    println!("crane {} can spell {} with:", crane.name(), target);
+                    solution.iter().for_each(|m| println!("{}", m));
+                }
+                None => println!(
+                    "crane {} can't spell {} in {} moves or less",
+                    crane.name(),
+                    target,
+                    max_moves
+                ),
+            }
+            continue;
+        }
+        if lenient {
+            stacks.validation = Validation::Lenient;
+        }
+        if !after.is_empty() || undo > 0 {
+            let mut history = History::new(stacks.clone(), crane.as_ref());
+            moves
+                .iter()
+                .try_for_each(|m| history.apply(m.clone()))
+                .expect("the moves should be valid");
+            let mut undone = 0;
+            while undone < undo && history.undo() {
+                undone += 1;
+            }
+            let mut redone = 0;
+            while redone < redo && history.redo() {
+                redone += 1;
+            }
+            if undo > 0 {
+                print!(
+                    "crane {} has the message {} with the last {} moves undone and {} redone\n{}",
+                    crane.name(),
+                    history.stacks().message(),
+                    undone,
+                    redone,
+                    history.stacks()
+                );
+            }
+            for k in &after {
+                let Some(message) = history.message_after(*k).map(|m| m.to_string()) else {
+                    println!("there is no move {}", k);
+                    continue;
+                };
+                history.rewind_to(*k);
+                print!(
+                    "crane {} has the message {} after move {}\n{}",
+                    crane.name(),
+                    message,
+                    k,
+                    history.stacks()
+                );
+            }
+        }
+        if trace.includes(&crane.name()) {
+            let frames = trace_moves(&mut stacks, &moves, |s, m| crane.apply(s, m))
+                .expect("the moves should be valid");
+            print_trace(&crane.name(), &moves, &frames);
+        } else {
+            moves
+                .iter()
+                .try_for_each(|m| crane.apply(&mut stacks, m))
+                .expect("the moves should be valid");
+        }
+        println!(
+            "crane {} leaves the message {}",
+            crane.name(),
+            stacks.message()
+        );
+    }
+}
+
+/// A model of crane, which decides how the crates of a `Move` get carried
+/// from one stack to another.
+trait Crane {
+    /// The name the crane is selected by on the command line.
+    fn name(&self) -> String;
+
+    fn apply(&self, stacks: &mut CrateStacks, the_move: &Move) -> Result<(), MoveError>;
+}
+
+/// Lifts one crate at a time, so the moved crates end up reversed.
+struct CrateMover9000;
+
+impl Crane for CrateMover9000 {
+    fn name(&self) -> String {
+        "9000".to_string()
+    }
+
+    fn apply(&self, stacks: &mut CrateStacks, the_move: &Move) -> Result<(), MoveError> {
+        stacks.apply_move(the_move)
+    }
+}
+
+/// Lifts all of the crates at once, so they keep their order.
+struct CrateMover9001;
+
+impl Crane for CrateMover9001 {
+    fn name(&self) -> String {
+        "9001".to_string()
+    }
+
+    fn apply(&self, stacks: &mut CrateStacks, the_move: &Move) -> Result<(), MoveError> {
+        stacks.apply_move_with_multiple_crates(the_move)
+    }
+}
+
+/// Like the CrateMover 9001, but can only lift `capacity` crates at once.
+/// Larger moves are split up into several lifts.
+struct LimitedCrane {
+    capacity: usize,
+}
+
+impl Crane for LimitedCrane {
+    fn name(&self) -> String {
+        format!("limited:{}", self.capacity)
+    }
+
+    fn apply(&self, stacks: &mut CrateStacks, the_move: &Move) -> Result<(), MoveError> {
+        let (from, to, mut count) = stacks.resolve(the_move)?;
+        while count > 0 {
+            let lifted = count.min(self.capacity);
+            stacks.lift(from, to, lifted);
+            count -= lifted;
+        }
+        Ok(())
+    }
+}
+
+/// Lifts all of the crates at once, but sets them down with each pair of
+/// crates swapped, so every other crate changes places with its neighbor.
+struct PairwiseCrane;
+
+impl Crane for PairwiseCrane {
+    fn name(&self) -> String {
+        "pairwise".to_string()
+    }
+
+    fn apply(&self, stacks: &mut CrateStacks, the_move: &Move) -> Result<(), MoveError> {
+        let (from, to, count) = stacks.resolve(the_move)?;
+        let start = stacks.stacks[to].len();
+        stacks.lift(from, to, count);
+        stacks.stacks[to][start..]
+            .chunks_exact_mut(2)
+            .for_each(|pair| pair.swap(0, 1));
+        Ok(())
+    }
+}
+
+/// Looks up a crane by the name it goes by on the command line:
+/// `9000`, `9001`, `pairwise`, or `limited:<capacity>`.
+fn crane_by_name(name: &str) -> Option<Box<dyn Crane>> {
+    match name {
+        "9000" => Some(Box::new(CrateMover9000)),
+        "9001" => Some(Box::new(CrateMover9001)),
+        "pairwise" => Some(Box::new(PairwiseCrane)),
+        _ => {
+            let capacity: usize = name.strip_prefix("limited:")?.parse().ok()?;
+            if capacity == 0 {
+                return None;
+            }
+            Some(Box::new(LimitedCrane { capacity }))
+        }
+    }
+}
+
+/// Which cranes should have their moves traced.
+///
+/// Selected on the command line with `--trace` (every crane), or with
+/// `--trace=<crane>` for specific ones.
+#[derive(Debug, PartialEq)]
+enum TraceMode {
+    Off,
+    All,
+    Only(Vec<String>),
+}
+
+impl TraceMode {
+    fn from_args<I: Iterator<Item = String>>(args: I) -> Self {
+        let mut mode = Self::Off;
+        for arg in args {
+            mode = match (mode, arg.strip_prefix("--trace")) {
+                (_, Some("")) | (Self::All, Some(_)) => Self::All,
+                (mode, Some(named)) if named.starts_with('=') => {
+                    let mut names = match mode {
+                        Self::Only(names) => names,
+                        _ => vec![],
+                    };
+                    names.push(named[1..].to_string());
+                    Self::Only(names)
+                }
+                (mode, _) => mode,
+            }
+        }
+        mode
+    }
+
+    fn includes(&self, crane: &str) -> bool {
+        match self {
+            Self::Off => false,
+            Self::All => true,
+            Self::Only(names) => names.iter().any(|n| n == crane),
+        }
+    }
+}
+
+/// Applies each move in turn, recording a drawing of the stacks after every
+/// one of them. The first frame is the drawing before any moves were made,
+/// so frame `k` shows the state after `k` moves.
+fn trace_moves<F>(
+    stacks: &mut CrateStacks,
+    moves: &[Move],
+    mut apply: F,
+) -> Result<Vec<String>, MoveError>
+where
+    F: FnMut(&mut CrateStacks, &Move) -> Result<(), MoveError>,
+{
+    let mut frames = Vec::with_capacity(moves.len() + 1);
+    frames.push(stacks.to_string());
     for a_move in moves {
-        stack.apply_move(&a_move);
-        stack2.apply_move_with_multiple_crates(&a_move);
+        apply(stacks, a_move)?;
+        frames.push(stacks.to_string());
+    }
+    Ok(frames)
+}
+
+fn print_trace(crane: &str, moves: &[Move], frames: &[String]) {
+    println!("tracing the {}", crane);
+    for (i, (a_move, frame)) in moves.iter().zip(frames.iter().skip(1)).enumerate() {
+        print!("step {}: {}\n{}\n", i + 1, a_move, frame);
+    }
+}
+
+/// How often `History` keeps a full copy of the stacks, in moves.
+const CHECKPOINT_EVERY: usize = 64;
+
+/// What a move did to the stacks, so that it can be taken back again
+/// whichever crane carried it out.
+#[derive(Debug, Clone)]
+struct Step {
+    the_move: Move,
+    from: usize,
+    to: usize,
+    /// The crates that were taken off the top of `from`, bottom first.
+    taken: Vec<Crated>,
+    /// How many crates were set down on top of `to`.
+    landed: usize,
+}
+
+/// Stacks that remember the moves made on them, so that any number of them
+/// can be rewound and replayed.
+///
+/// Each move keeps the crates it took, which is enough to undo it, and a
+/// copy of the stacks is kept every `CHECKPOINT_EVERY` moves so a long
+/// rewind doesn't have to go one move at a time.
+struct History<'a> {
+    crane: &'a dyn Crane,
+    /// The stacks after the first `applied` moves.
+    current: CrateStacks,
+    steps: Vec<Step>,
+    /// The stacks after every `CHECKPOINT_EVERY` moves, starting with the
+    /// ones before any moves.
+    checkpoints: Vec<CrateStacks>,
+    /// The message after each move, starting with the one before any moves.
+    messages: Vec<String>,
+    /// How many moves have been applied to get to the current stacks. Any
+    /// steps past this point have been undone, and can be redone.
+    applied: usize,
+}
+
+impl<'a> History<'a> {
+    fn new(stacks: CrateStacks, crane: &'a dyn Crane) -> Self {
+        Self {
+            crane,
+            messages: vec![stacks.message()],
+            checkpoints: vec![stacks.clone()],
+            current: stacks,
+            steps: vec![],
+            applied: 0,
+        }
+    }
+
+    fn stacks(&self) -> &CrateStacks {
+        &self.current
+    }
+
+    /// Applies a new move. Any moves that were undone are forgotten.
+    fn apply(&mut self, the_move: Move) -> Result<(), MoveError> {
+        let (from, to, count) = self.current.resolve(&the_move)?;
+        let from_stack = &self.current.stacks[from];
+        let taken = from_stack[from_stack.len() - count..].to_vec();
+        let before = self.current.stacks[to].len();
+        self.crane.apply(&mut self.current, &the_move)?;
+        let landed = self.current.stacks[to].len() - before;
+
+        self.steps.truncate(self.applied);
+        self.messages.truncate(self.applied + 1);
+        self.checkpoints
+            .truncate(self.applied / CHECKPOINT_EVERY + 1);
+        self.steps.push(Step {
+            the_move,
+            from,
+            to,
+            taken,
+            landed,
+        });
+        self.messages.push(self.current.message());
+        self.applied += 1;
+        if self.applied % CHECKPOINT_EVERY == 0 {
+            self.checkpoints.push(self.current.clone());
+        }
+        Ok(())
+    }
+
+    /// Takes back the last applied move. Returns false if there wasn't one.
+    fn undo(&mut self) -> bool {
+        let Some(step) = self.applied.checked_sub(1).map(|i| &self.steps[i]) else {
+            return false;
+        };
+        let to = &mut self.current.stacks[step.to];
+        to.truncate(to.len() - step.landed);
+        self.current.stacks[step.from].extend_from_slice(&step.taken);
+        self.applied -= 1;
+        true
+    }
+
+    /// Applies the most recently undone move again. Returns false if there
+    /// wasn't one.
+    fn redo(&mut self) -> bool {
+        let Some(step) = self.steps.get(self.applied) else {
+            return false;
+        };
+        self.crane
+            .apply(&mut self.current, &step.the_move)
+            .expect("the move worked the first time");
+        self.applied += 1;
+        true
+    }
+
+    /// Puts the stacks back the way they were after the first `k` moves,
+    /// starting over from the nearest checkpoint when that's closer.
+    fn rewind_to(&mut self, k: usize) {
+        assert!(
+            k <= self.steps.len(),
+            "there are only {} moves",
+            self.steps.len()
+        );
+        let checkpoint = k / CHECKPOINT_EVERY;
+        if k.abs_diff(self.applied) > k % CHECKPOINT_EVERY {
+            self.current = self.checkpoints[checkpoint].clone();
+            self.applied = checkpoint * CHECKPOINT_EVERY;
+        }
+        while self.applied > k {
+            self.undo();
+        }
+        while self.applied < k {
+            self.redo();
+        }
     }
-    println!("read out the message {}", stack.message());
-    println!("moving multiple: {}", stack2.message());
+
+    /// The message on top of the stacks after `k` moves, whether or not
+    /// they're currently applied.
+    fn message_after(&self, k: usize) -> Option<&str> {
+        self.messages.get(k).map(|m| m.as_str())
+    }
+}
+
+/// Searches breadth first for a shortest list of moves that leaves `target`
+/// as the message on top of the stacks, when carried out by `crane`.
+///
+/// The search space grows very quickly, so it gives up on lists longer than
+/// `max_moves`.
+fn solve_for_message(
+    start: &CrateStacks,
+    target: &str,
+    crane: &dyn Crane,
+    max_moves: usize,
+) -> Option<Vec<Move>> {
+    let mut start = start.clone();
+    start.validation = Validation::Strict;
+    if start.message() == target {
+        return Some(vec![]);
+    }
+
+    let mut seen = HashSet::from([start.stacks.clone()]);
+    let mut queue = VecDeque::from([(start, vec![])]);
+    while let Some((stacks, moves)) = queue.pop_front() {
+        if moves.len() == max_moves {
+            continue;
+        }
+        for a_move in stacks.possible_moves() {
+            let mut next = stacks.clone();
+            crane
+                .apply(&mut next, &a_move)
+                .expect("possible moves are valid");
+            if !seen.insert(next.stacks.clone()) {
+                continue;
+            }
+            let mut next_moves = moves.clone();
+            next_moves.push(a_move);
+            if next.message() == target {
+                return Some(next_moves);
+            }
+            queue.push_back((next, next_moves));
+        }
+    }
+    None
+}
+
+/// How `CrateStacks` reacts to a move that can't be carried out exactly as
+/// it was written.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+enum Validation {
+    /// Every questionable move is an error.
+    Strict,
+    /// Moves as many crates as there are, and treats moving a stack onto
+    /// itself as doing nothing. Stacks that don't exist are still an error.
+    Lenient,
+}
+
+#[derive(Error, Debug, PartialEq)]
+enum MoveError {
+    #[error("there is no stack {0}")]
+    BadIndex(usize),
+    #[error("stack {stack} only has {available} crates, but {needed} should be moved")]
+    InsufficientCrates {
+        stack: usize,
+        needed: usize,
+        available: usize,
+    },
+    #[error("can't move crates from stack {0} onto itself")]
+    SameStack(usize),
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
 struct CrateStacks {
     stacks: Vec<Vec<Crated>>,
+    validation: Validation,
 }
 
 impl CrateStacks {
-    fn construct(from: &Vec<Vec<Option<Crated>>>) -> Option<Self> {
+    fn construct(from: &[Vec<Option<Crated>>]) -> Option<Self> {
         let width = from[0].len();
         if !from.iter().map(|r| r.len()).all(|len| len == width) {
             return None;
@@ -56,40 +536,154 @@ impl CrateStacks {
                 }
             }
         }
-        return Some(Self { stacks });
+        Some(Self {
+            stacks,
+            validation: Validation::Strict,
+        })
     }
 
-    fn apply_move(&mut self, the_move: &Move) {
-        let from = (the_move.from_index - 1).to_usize();
-        let to = (the_move.to_index - 1).to_usize();
-        for _ in 0..the_move.count {
-            if let Some(v) = self.stacks[from].pop() {
-                self.stacks[to].push(v);
+    /// Checks a move against the current stacks. Gives back the zero-based
+    /// indexes of the two stacks, and how many crates should really move.
+    fn resolve(&self, the_move: &Move) -> Result<(usize, usize, usize), MoveError> {
+        let index = |i: usize| match i {
+            0 => Err(MoveError::BadIndex(i)),
+            n if n > self.stacks.len() => Err(MoveError::BadIndex(i)),
+            n => Ok(n - 1),
+        };
+        let from = index(the_move.from_index)?;
+        let to = index(the_move.to_index)?;
+        let strict = self.validation == Validation::Strict;
+
+        if from == to {
+            if strict {
+                return Err(MoveError::SameStack(the_move.from_index));
             }
+            return Ok((from, to, 0));
         }
+
+        let available = self.stacks[from].len();
+        let count = the_move.count;
+        if strict && count > available {
+            return Err(MoveError::InsufficientCrates {
+                stack: the_move.from_index,
+                needed: the_move.count,
+                available,
+            });
+        }
+        Ok((from, to, count.min(available)))
     }
 
-    fn apply_move_with_multiple_crates(&mut self, the_move: &Move) {
-        let from_idx = (the_move.from_index - 1).to_usize();
-        let to_idx = (the_move.to_index - 1).to_usize();
+    fn apply_move(&mut self, the_move: &Move) -> Result<(), MoveError> {
+        let (from_idx, to_idx, count) = self.resolve(the_move)?;
+        if count == 0 {
+            return Ok(());
+        }
+        // popping and pushing one crate at a time is the same as moving
+        // them all at once, in reverse.
+        let (from, to) = self.pair_mut(from_idx, to_idx);
+        to.extend(from.drain(from.len() - count..).rev());
+        Ok(())
+    }
 
-        let from = &mut self.stacks[from_idx];
-        let mut moved = from.split_off(0.max(from.len() - the_move.count.to_usize()));
+    fn apply_move_with_multiple_crates(&mut self, the_move: &Move) -> Result<(), MoveError> {
+        let (from_idx, to_idx, count) = self.resolve(the_move)?;
+        self.lift(from_idx, to_idx, count);
+        Ok(())
+    }
 
-        self.stacks[to_idx].append(&mut moved);
+    /// Moves the top `count` crates in one go, keeping their order. The
+    /// indexes and count should already have been checked by `resolve`.
+    fn lift(&mut self, from_idx: usize, to_idx: usize, count: usize) {
+        if count == 0 {
+            return;
+        }
+        let (from, to) = self.pair_mut(from_idx, to_idx);
+        to.extend(from.drain(from.len() - count..));
+    }
+
+    /// Borrows two different stacks at once, so crates can be drained from
+    /// one straight into the other without a temporary buffer.
+    fn pair_mut(&mut self, a: usize, b: usize) -> (&mut Vec<Crated>, &mut Vec<Crated>) {
+        assert_ne!(a, b, "can't borrow the same stack twice");
+        if a < b {
+            let (left, right) = self.stacks.split_at_mut(b);
+            (&mut left[a], &mut right[0])
+        } else {
+            let (left, right) = self.stacks.split_at_mut(a);
+            (&mut right[0], &mut left[b])
+        }
+    }
+
+    /// Every move that can be made without an error: any number of crates,
+    /// from any stack that has some, onto any other stack.
+    fn possible_moves(&self) -> Vec<Move> {
+        let width = self.stacks.len();
+        let mut moves = vec![];
+        for (from, stack) in self.stacks.iter().enumerate() {
+            for to in (0..width).filter(|to| *to != from) {
+                for count in 1..=stack.len() {
+                    moves.push(Move {
+                        count,
+                        from_index: from + 1,
+                        to_index: to + 1,
+                    });
+                }
+            }
+        }
+        moves
     }
 
     fn message(&self) -> String {
         self.stacks
             .iter()
-            .map(|s| s.last())
-            .flatten()
+            .filter_map(|s| s.last())
             .map(|c| c.letter)
             .collect()
     }
 }
 
-#[derive(Debug, Clone)]
+/// Renders the stacks as the same ASCII drawing the puzzle input uses,
+/// so that the output can be fed back through `parse_drawing`.
+impl Display for CrateStacks {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // the parser needs at least one row of crates, even if it's empty.
+        let height = self
+            .stacks
+            .iter()
+            .map(|s| s.len())
+            .max()
+            .unwrap_or(0)
+            .max(1);
+        for level in (0..height).rev() {
+            let row: Vec<String> = self
+                .stacks
+                .iter()
+                .map(|s| match s.get(level) {
+                    Some(c) => format!("[{}]", c.letter),
+                    None => "   ".to_string(),
+                })
+                .collect();
+            writeln!(f, "{}", row.join(" "))?;
+        }
+        // each label starts under the letter of its crates, so that they
+        // stay lined up until the labels get longer than the crates, past
+        // 999 stacks.
+        let mut labels = String::new();
+        for i in 0..self.stacks.len() {
+            let column = 4 * i + 1;
+            if labels.len() < column {
+                labels.push_str(&" ".repeat(column - labels.len()));
+            } else {
+                labels.push(' ');
+            }
+            labels.push_str(&(i + 1).to_string());
+        }
+        let width = (4 * self.stacks.len()).saturating_sub(1);
+        writeln!(f, "{:<width$}", labels, width = width)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 struct Crated {
     letter: char,
 }
@@ -104,7 +698,7 @@ fn one_alpha(i: &str) -> nom::IResult<&str, char> {
 
 fn parse_crate_or_space(i: &str) -> nom::IResult<&str, Option<Crated>> {
     nom::branch::alt((
-        nom::combinator::map(parse_crate, |c| Some(c)),
+        nom::combinator::map(parse_crate, Some),
         nom::combinator::map(tag("   "), |_| None),
     ))(i)
 }
@@ -116,34 +710,43 @@ fn parse_crates_line(i: &str) -> nom::IResult<&str, Vec<Option<Crated>>> {
     )(i)
 }
 
-fn parse_crate_index(i: &str) -> nom::IResult<&str, u8> {
+fn parse_number(i: &str) -> nom::IResult<&str, usize> {
+    nom::combinator::map_res(digit1, str::parse)(i)
+}
+
+fn parse_crate_indexes_line(i: &str) -> nom::IResult<&str, Vec<usize>> {
     delimited(
-        tag(" "),
-        nom::character::complete::u8,
-        nom::combinator::opt(tag(" ")),
+        space0,
+        separated_list1(space1, parse_number),
+        terminated(space0, nom::character::complete::line_ending),
     )(i)
 }
 
-fn parse_crate_indexes_line(i: &str) -> nom::IResult<&str, Vec<u8>> {
-    terminated(
-        separated_list1(tag(" "), parse_crate_index),
-        nom::character::complete::line_ending,
-    )(i)
+type Drawing = (Vec<Vec<Option<Crated>>>, Vec<usize>);
+
+fn parse_drawing(i: &str) -> nom::IResult<&str, Drawing> {
+    nom::sequence::tuple((many1(parse_crates_line), parse_crate_indexes_line))(i)
 }
 
-fn parse_file(i: &str) -> nom::IResult<&str, (Vec<Vec<Option<Crated>>>, Vec<u8>, Vec<Move>)> {
-    nom::sequence::tuple((
-        many1(parse_crates_line),
-        terminated(parse_crate_indexes_line, line_ending),
-        many1(parse_move),
-    ))(i)
+fn parse_file(i: &str) -> nom::IResult<&str, (Drawing, Vec<Move>)> {
+    nom::sequence::tuple((terminated(parse_drawing, line_ending), many1(parse_move)))(i)
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
 struct Move {
-    count: u8,
-    from_index: u8,
-    to_index: u8,
+    count: usize,
+    from_index: usize,
+    to_index: usize,
+}
+
+impl Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "move {} from {} to {}",
+            self.count, self.from_index, self.to_index
+        )
+    }
 }
 
 fn parse_move(i: &str) -> nom::IResult<&str, Move> {
@@ -151,15 +754,15 @@ fn parse_move(i: &str) -> nom::IResult<&str, Move> {
         nom::sequence::tuple((
             tag("move"),
             space1,
-            nom::character::complete::u8,
+            parse_number,
             space1,
             tag("from"),
             space1,
-            nom::character::complete::u8,
+            parse_number,
             space1,
             tag("to"),
             space1,
-            nom::character::complete::u8,
+            parse_number,
             nom::combinator::opt(space0),
             nom::combinator::opt(line_ending),
         )),
@@ -173,7 +776,23 @@ fn parse_move(i: &str) -> nom::IResult<&str, Move> {
 
 #[cfg(test)]
 mod tests {
-    use crate::{parse_crate_indexes_line, parse_move, Move};
+    use crate::{
+        crane_by_name, parse_crate_indexes_line, parse_drawing, parse_file, parse_move,
+        solve_for_message, trace_moves, Crane, CrateMover9000, CrateMover9001, CrateStacks, Crated,
+        History, Move, MoveError, TraceMode, Validation, CHECKPOINT_EVERY,
+    };
+
+    const EXAMPLE: &str = concat!(
+        "    [D]    \n",
+        "[N] [C]    \n",
+        "[Z] [M] [P]\n",
+        " 1   2   3 \n",
+        "\n",
+        "move 1 from 2 to 1\n",
+        "move 3 from 1 to 3\n",
+        "move 2 from 2 to 1\n",
+        "move 1 from 1 to 2\n",
+    );
 
     #[test]
     fn parsing_indexes() {
@@ -197,4 +816,358 @@ mod tests {
 
         parse_move("move 1 from 1 to 1             \n").unwrap();
     }
+
+    #[test]
+    fn rendering_round_trips() {
+        let drawing = "    [D]    \n[N] [C]    \n[Z] [M] [P]\n 1   2   3 \n";
+        let (_, (crate_def, _)) = parse_drawing(drawing).unwrap();
+        let stacks = CrateStacks::construct(&crate_def).unwrap();
+        let rendered = stacks.to_string();
+        assert_eq!(rendered, drawing);
+
+        let (rest, (crate_def, indexes)) = parse_drawing(&rendered).unwrap();
+        assert_eq!(rest, "");
+        assert_eq!(indexes, vec![1, 2, 3]);
+        assert_eq!(CrateStacks::construct(&crate_def).unwrap(), stacks);
+    }
+
+    #[test]
+    fn rendering_empty_stacks() {
+        let stacks = CrateStacks {
+            stacks: vec![vec![], vec![]],
+            validation: Validation::Strict,
+        };
+        let rendered = stacks.to_string();
+        let (_, (crate_def, _)) = parse_drawing(&rendered).unwrap();
+        assert_eq!(CrateStacks::construct(&crate_def).unwrap(), stacks);
+    }
+
+    #[test]
+    fn tracing_moves() {
+        let (_, ((crate_def, _), moves)) = parse_file(EXAMPLE).unwrap();
+        let mut stacks = CrateStacks::construct(&crate_def).unwrap();
+        let frames = trace_moves(&mut stacks, &moves, CrateStacks::apply_move).unwrap();
+        assert_eq!(frames.len(), moves.len() + 1);
+        assert_eq!(
+            frames[0],
+            "    [D]    \n[N] [C]    \n[Z] [M] [P]\n 1   2   3 \n"
+        );
+        assert_eq!(
+            frames[1],
+            "[D]        \n[N] [C]    \n[Z] [M] [P]\n 1   2   3 \n"
+        );
+        assert_eq!(frames[4], stacks.to_string());
+        assert_eq!(stacks.message(), "CMZ");
+
+        let mut stacks = CrateStacks::construct(&crate_def).unwrap();
+        let frames = trace_moves(
+            &mut stacks,
+            &moves,
+            CrateStacks::apply_move_with_multiple_crates,
+        )
+        .unwrap();
+        assert_eq!(
+            frames[2],
+            "        [D]\n        [N]\n    [C] [Z]\n    [M] [P]\n 1   2   3 \n"
+        );
+        assert_eq!(stacks.message(), "MCD");
+    }
+
+    #[test]
+    fn trace_mode_from_args() {
+        let mode = |args: &[&str]| TraceMode::from_args(args.iter().map(|a| a.to_string()));
+        assert_eq!(mode(&[]), TraceMode::Off);
+        assert_eq!(mode(&["--crane=9000"]), TraceMode::Off);
+        assert_eq!(mode(&["--trace"]), TraceMode::All);
+        assert_eq!(mode(&["--trace=9000", "--trace"]), TraceMode::All);
+        assert_eq!(mode(&["--tracer"]), TraceMode::Off);
+
+        let only = mode(&["--trace=9001", "--trace=limited:2"]);
+        assert!(only.includes("9001"));
+        assert!(only.includes("limited:2"));
+        assert!(!only.includes("9000"));
+    }
+
+    fn example_stacks() -> CrateStacks {
+        let (_, ((crate_def, _), _)) = parse_file(EXAMPLE).unwrap();
+        CrateStacks::construct(&crate_def).unwrap()
+    }
+
+    fn a_move(count: usize, from_index: usize, to_index: usize) -> Move {
+        Move {
+            count,
+            from_index,
+            to_index,
+        }
+    }
+
+    #[test]
+    fn strict_moves_are_validated() {
+        let mut stacks = example_stacks();
+        for apply in [
+            CrateStacks::apply_move,
+            CrateStacks::apply_move_with_multiple_crates,
+        ] {
+            assert_eq!(
+                apply(&mut stacks, &a_move(1, 0, 1)),
+                Err(MoveError::BadIndex(0))
+            );
+            assert_eq!(
+                apply(&mut stacks, &a_move(1, 1, 4)),
+                Err(MoveError::BadIndex(4))
+            );
+            assert_eq!(
+                apply(&mut stacks, &a_move(1, 2, 2)),
+                Err(MoveError::SameStack(2))
+            );
+            assert_eq!(
+                apply(&mut stacks, &a_move(4, 2, 1)),
+                Err(MoveError::InsufficientCrates {
+                    stack: 2,
+                    needed: 4,
+                    available: 3
+                })
+            );
+        }
+        assert_eq!(stacks, example_stacks(), "failed moves change nothing");
+    }
+
+    #[test]
+    fn lenient_moves_do_what_they_can() {
+        let mut stacks = example_stacks();
+        stacks.validation = Validation::Lenient;
+        stacks.apply_move(&a_move(1, 2, 2)).unwrap();
+        stacks.apply_move(&a_move(5, 2, 1)).unwrap();
+        assert_eq!(stacks.message(), "MP");
+        assert_eq!(
+            stacks.apply_move(&a_move(1, 9, 1)),
+            Err(MoveError::BadIndex(9))
+        );
+
+        let mut stacks = example_stacks();
+        stacks.validation = Validation::Lenient;
+        stacks
+            .apply_move_with_multiple_crates(&a_move(5, 1, 3))
+            .unwrap();
+        assert_eq!(stacks.message(), "DN");
+    }
+
+    #[test]
+    fn cranes_by_name() {
+        assert!(crane_by_name("9002").is_none());
+        assert!(crane_by_name("limited:0").is_none());
+        assert!(crane_by_name("limited:").is_none());
+        for name in ["9000", "9001", "pairwise", "limited:3"] {
+            assert_eq!(crane_by_name(name).unwrap().name(), name);
+        }
+    }
+
+    fn run_crane(name: &str, stacks: &mut CrateStacks, moves: &[Move]) {
+        let crane = crane_by_name(name).unwrap();
+        for m in moves {
+            crane.apply(stacks, m).unwrap();
+        }
+    }
+
+    #[test]
+    fn crane_models() {
+        let (_, ((crate_def, _), moves)) = parse_file(EXAMPLE).unwrap();
+        let message = |name: &str| {
+            let mut stacks = CrateStacks::construct(&crate_def).unwrap();
+            run_crane(name, &mut stacks, &moves);
+            stacks.message()
+        };
+        assert_eq!(message("9000"), "CMZ");
+        assert_eq!(message("9001"), "MCD");
+        assert_eq!(
+            message("limited:1"),
+            "CMZ",
+            "lifting one at a time is a 9000"
+        );
+        assert_eq!(message("limited:3"), "MCD", "never needs more than 3");
+
+        let mut stacks = example_stacks();
+        run_crane("limited:2", &mut stacks, &[a_move(3, 2, 1)]);
+        assert_eq!(
+            stacks.to_string(),
+            "[M]        \n[D]        \n[C]        \n[N]        \n[Z]     [P]\n 1   2   3 \n"
+        );
+
+        let mut stacks = example_stacks();
+        run_crane("pairwise", &mut stacks, &[a_move(3, 2, 3)]);
+        assert_eq!(
+            stacks.to_string(),
+            "        [D]\n        [M]\n[N]     [C]\n[Z]     [P]\n 1   2   3 \n"
+        );
+    }
+
+    /// `width` stacks of `height` crates each, lettered A to Z from the
+    /// bottom up, over and over.
+    fn synthetic_stacks(width: usize, height: usize) -> CrateStacks {
+        let stack: Vec<Crated> = (0..height)
+            .map(|i| Crated {
+                letter: (b'A' + (i % 26) as u8) as char,
+            })
+            .collect();
+        CrateStacks {
+            stacks: vec![stack; width],
+            validation: Validation::Strict,
+        }
+    }
+
+    #[test]
+    fn more_stacks_than_fit_in_a_byte() {
+        let stacks = synthetic_stacks(300, 2);
+        let rendered = stacks.to_string();
+        let (_, (crate_def, indexes)) = parse_drawing(&rendered).unwrap();
+        assert_eq!(indexes, (1..=300).collect::<Vec<usize>>());
+        assert_eq!(CrateStacks::construct(&crate_def).unwrap(), stacks);
+
+        // every label starts right under the letters of its stack.
+        let lines: Vec<&str> = rendered.lines().collect();
+        let labels = lines[lines.len() - 1];
+        for (i, stack) in stacks.stacks.iter().enumerate() {
+            let column = 4 * i + 1;
+            let label = (i + 1).to_string();
+            assert_eq!(&labels[column..column + label.len()], label);
+            assert_eq!(
+                lines[0].chars().nth(column),
+                Some(stack.last().unwrap().letter)
+            );
+        }
+
+        let (_, big) = parse_move("move 1000 from 300 to 256").unwrap();
+        assert_eq!(big, a_move(1000, 300, 256));
+    }
+
+    #[test]
+    fn moving_millions_of_crates() {
+        let height = 2_000_000;
+        let original = synthetic_stacks(3, height);
+        let top = original.message();
+        for (name, moved_top) in [
+            ("9000", "A"),
+            ("9001", &top[..1]),
+            // the last lift is the bottom 1000 crates, topped by crate 999.
+            ("limited:1000", "L"),
+        ] {
+            let mut stacks = synthetic_stacks(3, height);
+            run_crane(name, &mut stacks, &[a_move(height, 1, 2)]);
+            assert_eq!(stacks.stacks[1].len(), 2 * height);
+            assert_eq!(
+                stacks.message(),
+                format!("{}{}", moved_top, &top[2..]),
+                "crane {}",
+                name
+            );
+
+            run_crane(name, &mut stacks, &[a_move(height, 2, 1)]);
+            assert_eq!(stacks, original, "crane {} there and back", name);
+        }
+
+        let mut stacks = synthetic_stacks(3, height);
+        run_crane("pairwise", &mut stacks, &[a_move(height, 1, 3)]);
+        run_crane("pairwise", &mut stacks, &[a_move(height, 3, 1)]);
+        assert_eq!(stacks, original, "swapping pairs twice undoes itself");
+    }
+
+    #[test]
+    fn solving_for_a_message() {
+        let stacks = example_stacks();
+        assert_eq!(
+            solve_for_message(&stacks, "NDP", &CrateMover9000, 3),
+            Some(vec![]),
+            "already there"
+        );
+        assert_eq!(
+            solve_for_message(&stacks, "ZNP", &CrateMover9000, 3),
+            Some(vec![a_move(1, 1, 2)])
+        );
+        assert_eq!(
+            solve_for_message(&stacks, "CMP", &CrateMover9000, 3),
+            Some(vec![a_move(2, 2, 1)])
+        );
+        assert_eq!(
+            solve_for_message(&stacks, "DMP", &CrateMover9001, 3),
+            Some(vec![a_move(2, 2, 1)])
+        );
+        assert_eq!(
+            solve_for_message(&stacks, "QQQ", &CrateMover9000, 2),
+            None,
+            "there is no Q crate"
+        );
+
+        // the puzzle's own moves should be matched or beaten, and whatever
+        // the solver comes up with has to actually spell the message.
+        let (_, (_, moves)) = parse_file(EXAMPLE).unwrap();
+        for (crane, message) in [
+            (&CrateMover9000 as &dyn Crane, "CMZ"),
+            (&CrateMover9001, "MCD"),
+        ] {
+            let solution = solve_for_message(&stacks, message, crane, moves.len()).unwrap();
+            assert!(solution.len() <= moves.len());
+
+            let mut replayed = stacks.clone();
+            for m in &solution {
+                crane.apply(&mut replayed, m).unwrap();
+            }
+            assert_eq!(replayed.message(), message);
+        }
+    }
+
+    #[test]
+    fn rewinding_and_replaying() {
+        let (_, (_, moves)) = parse_file(EXAMPLE).unwrap();
+        let mut history = History::new(example_stacks(), &CrateMover9000);
+        assert!(!history.undo(), "nothing to undo yet");
+        for m in &moves {
+            history.apply(m.clone()).unwrap();
+        }
+        assert_eq!(history.stacks().message(), "CMZ");
+
+        let frames = trace_moves(&mut example_stacks(), &moves, CrateStacks::apply_move).unwrap();
+        for (k, frame) in frames.iter().enumerate() {
+            history.rewind_to(k);
+            assert_eq!(&history.stacks().to_string(), frame, "after {} moves", k);
+        }
+
+        history.rewind_to(1);
+        assert!(history.undo());
+        assert_eq!(history.stacks(), &example_stacks());
+        assert!(history.redo());
+        assert!(history.redo());
+        assert_eq!(history.stacks().to_string(), frames[2]);
+        assert_eq!(history.message_after(0), Some("NDP"));
+        assert_eq!(history.message_after(4), Some("CMZ"));
+        assert_eq!(history.message_after(5), None);
+
+        history.apply(a_move(1, 2, 3)).unwrap();
+        assert!(!history.redo(), "the undone moves were replaced");
+        let message = history.stacks().message();
+        assert_eq!(history.message_after(3), Some(message.as_str()));
+        assert_eq!(history.message_after(4), None);
+    }
+
+    #[test]
+    fn rewinding_past_checkpoints() {
+        // enough moves for a few checkpoints, some of which only move part
+        // of what they ask for or nothing at all.
+        let moves: Vec<Move> = (0..CHECKPOINT_EVERY * 3 + 5)
+            .map(|i| a_move(i % 4 + 1, i % 3 + 1, i * 2 % 3 + 1))
+            .collect();
+        let mut start = example_stacks();
+        start.validation = Validation::Lenient;
+        for crane in ["9000", "9001", "pairwise", "limited:2"] {
+            let crane = crane_by_name(crane).unwrap();
+            let mut history = History::new(start.clone(), crane.as_ref());
+            for m in &moves {
+                history.apply(m.clone()).unwrap();
+            }
+            let frames = trace_moves(&mut start.clone(), &moves, |s, m| crane.apply(s, m)).unwrap();
+            for k in [moves.len(), 0, 130, 129, 64, 3, 191, 70, moves.len()] {
+                history.rewind_to(k);
+                assert_eq!(history.stacks().to_string(), frames[k], "after {} moves", k);
+            }
+        }
+    }
 }
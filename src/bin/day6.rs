@@ -1,34 +1,118 @@
-use std::{collections::HashSet, fs::File, io::Read};
+use std::{
+    env,
+    fs::File,
+    io::{self, Read},
+    process::exit,
+};
 
-fn main() {
-    let mut f = File::open("inputs/day6.txt").unwrap();
-    let mut s = String::new();
+use aoc::{
+    info, log,
+    marker::{all_distinct, segments, stream_markers},
+    trace,
+};
 
-    f.read_to_string(&mut s).unwrap();
+const PACKET_MARKER: usize = 4;
+const MESSAGE_MARKER: usize = 14;
 
-    let chars_processed = start_of_transmission(&s, 4);
-    if let Some(chars) = chars_processed {
-        println!("Processed {} chars", chars);
-    }
+/// Reads the datastream from the path given on the command line, or from
+/// stdin when it's `-`. With `--all=N` every marker of length N is listed,
+/// and with `--segment=N` the stream is split up at the markers instead.
+fn main() {
+    log::init();
+    let args: Vec<String> = env::args().skip(1).filter(|a| !log::is_flag(a)).collect();
+    let path = args
+        .iter()
+        .find(|a| !a.starts_with("--"))
+        .map_or("inputs/day6.txt", |a| a.as_str());
+    let input: Box<dyn Read> = if path == "-" {
+        Box::new(io::stdin().lock())
+    } else {
+        match File::open(path) {
+            Ok(f) => Box::new(f),
+            Err(err) => {
+                eprintln!("couldn't open {}: {}", path, err);
+                exit(1);
+            }
+        }
+    };
 
-    let chars_processed = start_of_transmission(&s, 14);
-    if let Some(chars) = chars_processed {
-        println!("Processed {} chars", chars);
+    let length_flag = |name: &str| {
+        args.iter()
+            .find_map(|a| a.strip_prefix(name))
+            .map(|n| match n.parse::<usize>() {
+                Ok(length) if length > 0 => length,
+                _ => {
+                    eprintln!("{} should be given a marker length of at least 1", name);
+                    exit(1);
+                }
+            })
+    };
+    let all = length_flag("--all=");
+    let segment = length_flag("--segment=");
+    if all.is_some() || segment.is_some() {
+        let mut stream = vec![];
+        let mut input = input;
+        input
+            .read_to_end(&mut stream)
+            .expect("couldn't read the datastream");
+        if let Some(length) = all {
+            let starts: Vec<usize> = all_distinct(&stream, length).map(|m| m.start).collect();
+            println!("{} markers of length {}", starts.len(), length);
+            trace!("they start at {:?}", starts);
+        }
+        if let Some(length) = segment {
+            for segment in segments(&stream, length) {
+                println!(
+                    "{}: {} {:?}",
+                    segment.start,
+                    String::from_utf8_lossy(segment.marker),
+                    String::from_utf8_lossy(segment.payload)
+                );
+            }
+        }
+        return;
     }
+
+    stream_markers(input, &[PACKET_MARKER, MESSAGE_MARKER], |length, end| {
+        info!("found the {}-character marker", length);
+        println!("Processed {} chars", end);
+    })
+    .expect("couldn't read the datastream");
 }
 
-fn start_of_transmission(code: &str, preamble_length: usize) -> Option<usize> {
-    let mut buf = Vec::with_capacity(preamble_length);
+#[cfg(test)]
+mod tests {
+    use aoc::marker::stream_markers;
 
-    for (i, b) in code.bytes().enumerate() {
-        if buf.len() < preamble_length {
-            buf.push(b);
-        } else {
-            buf[i % preamble_length] = b;
-            if buf.iter().copied().collect::<HashSet<u8>>().len() == preamble_length {
-                return Some(i + 1);
-            }
+    use crate::{MESSAGE_MARKER, PACKET_MARKER};
+
+    fn markers(stream: &str) -> Vec<(usize, usize)> {
+        let mut found = vec![];
+        stream_markers(
+            stream.as_bytes(),
+            &[PACKET_MARKER, MESSAGE_MARKER],
+            |length, end| found.push((length, end)),
+        )
+        .unwrap();
+        found
+    }
+
+    #[test]
+    fn puzzle_examples() {
+        let examples = [
+            ("mjqjpqmgbljsphdztnvjfqwrcgsmlb", 7, 19),
+            ("bvwbjplbgvbhsrlpgdmjqwftvncz", 5, 23),
+            ("nppdvjthqldpwncqszvftbrmjlhg", 6, 23),
+            ("nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg", 10, 29),
+            ("zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw", 11, 26),
+        ];
+        for (stream, packet, message) in examples {
+            assert_eq!(
+                markers(stream),
+                vec![(PACKET_MARKER, packet), (MESSAGE_MARKER, message)],
+                "{}",
+                stream
+            );
         }
     }
-    None
 }
@@ -1,9 +1,16 @@
+// `current_mut` predates the lints the rest of the crate is checked with.
+#![allow(unknown_lints, mismatched_lifetime_syntaxes)]
+
 use std::{cell::RefCell, collections::HashMap, fs::File, io::Read, rc::Rc};
 
-use aoc::shell_parse::{cd_cmd, commands, ls_cmd, ls_out_files};
+use aoc::{
+    info, log,
+    shell_parse::{cd_cmd, commands, ls_cmd, ls_out_files},
+};
 
 fn main() {
-    println!("day 7");
+    log::init();
+    info!("day 7");
 
     let mut f = File::open("inputs/day7.txt").expect("couldn't open file");
     let mut text = String::new();
@@ -31,7 +38,7 @@ fn main() {
     println!("the answer? {}", solution);
 
     let need_to_free = 30000000 - (70000000 - sizes[0]);
-    println!("need to free {}", need_to_free);
+    info!("need to free {}", need_to_free);
 
     let mut sizes = sizes;
     sizes.sort();
@@ -63,15 +70,13 @@ impl<'a> Command<'a> {
 
 #[derive(Debug)]
 struct Dir<'a> {
-    name: &'a str,
     subdirs: HashMap<&'a str, Rc<RefCell<Dir<'a>>>>,
     own_file_size: u64,
 }
 
 impl<'a> Dir<'a> {
-    fn new(name: &'a str) -> Dir<'a> {
+    fn new() -> Dir<'a> {
         Dir {
-            name,
             subdirs: HashMap::new(),
             own_file_size: 0,
         }
@@ -81,7 +86,7 @@ impl<'a> Dir<'a> {
         if let Some(dir) = self.subdirs.get(subdir_name) {
             Rc::clone(dir)
         } else {
-            let new_dir = Self::new(subdir_name);
+            let new_dir = Self::new();
             let r = Rc::new(RefCell::new(new_dir));
             self.subdirs.insert(subdir_name, Rc::clone(&r));
             r
@@ -111,7 +116,7 @@ struct FileTracker<'a> {
 
 impl<'a> FileTracker<'a> {
     fn new() -> Self {
-        let dir = Rc::new(RefCell::new(Dir::new("/")));
+        let dir = Rc::new(RefCell::new(Dir::new()));
         Self {
             root: Rc::clone(&dir),
             dir_stack: Vec::new(),
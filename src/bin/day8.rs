@@ -1,90 +1,674 @@
 use std::{
-    collections::HashSet,
-    fs::File,
-    io::{BufRead, BufReader},
+    env,
+    fs::{self, File},
+    io::{self, BufWriter, IsTerminal},
+    time::Instant,
 };
 
+use aoc::{
+    grid::{Direction, Grid, Pos},
+    image::{write_pgm, write_ppm},
+    info, log,
+    rng::XorShift,
+    trace,
+};
+use thiserror::Error;
+
 pub fn main() {
-    let map = read_tree_map("inputs/day8.txt");
-    let width = map.0;
-
-    let mut visibility = vec![0; width * width];
-
-    println!("the width is {}", map.0);
-    for dir in [
-        Direction::Left,
-        Direction::Down,
-        Direction::Right,
-        Direction::Up,
-    ] {
-        scan_map(&map, dir, |(y, x), last, cur| {
-            println!("y: {}, x: {}, last: {:?}, cur: {}", y, x, last, cur);
-            if let Some(last) = last {
-                if last >= cur {
-                    return last;
-                }
-            }
-            visibility[width * x + y] = 1;
-            cur
+    log::init();
+    if let Some(size) = env::args().find_map(|a| a.strip_prefix("--bench").map(String::from)) {
+        let size = size.strip_prefix('=').map_or(5000, |n| {
+            n.parse().expect("--bench should be given a number")
         });
+        bench(size);
+        return;
+    }
+
+    let args: Vec<String> = env::args().skip(1).collect();
+    let format = if args.iter().any(|a| a == "--separated") {
+        MapFormat::Separated
+    } else {
+        MapFormat::Digits
+    };
+    let map = match read_tree_map("inputs/day8.txt", format) {
+        Ok(map) => map,
+        Err(err) => {
+            eprintln!("couldn't read the map: {}", err);
+            std::process::exit(1);
+        }
+    };
+
+    info!("the map is {} by {}", map.width(), map.height());
+
+    let (visible, scores) = survey(&map);
+    println!(
+        "# of visible trees: {}",
+        visible.values().filter(|v| **v).count()
+    );
+
+    if let Some((pos, score)) = best_scenic_spot(&scores) {
+        println!(
+            "the best scenic score is {}, for the tree at row {}, col {}",
+            score, pos.row, pos.col
+        );
+    }
+
+    if let Some(from) = args.iter().find_map(|a| a.strip_prefix("--from=")) {
+        let pos = parse_pos(from).expect("--from should be given a row and column, like 3,2");
+        if !map.contains(pos) {
+            eprintln!("row {}, col {} is not on the map", pos.row, pos.col);
+            std::process::exit(1);
+        }
+        let directions: &[Direction] = if args.iter().any(|a| a == "--diagonals") {
+            &Direction::ALL
+        } else {
+            &Direction::CARDINAL
+        };
+        let seen = seen_from(&map, pos, directions);
+        println!(
+            "the tree at row {}, col {} can see {} trees",
+            pos.row,
+            pos.col,
+            seen.len()
+        );
+        trace!("it can see {:?}", seen);
+        let seen = seen_by(&map, pos, directions);
+        println!("and can be seen by {} trees", seen.len());
+        trace!("it can be seen by {:?}", seen);
     }
+    if args.iter().any(|a| a == "--render") {
+        let color = io::stdout().is_terminal();
+        print!("{}", render_visibility(&map, &visible, color));
+        println!();
+        print!("{}", render_heatmap(&scores, color));
+    }
+    if let Some(path) = args.iter().find_map(|a| a.strip_prefix("--ppm=")) {
+        let file = File::create(path).expect("couldn't create the image file");
+        write_ppm(BufWriter::new(file), &visibility_image(&map, &visible))
+            .expect("couldn't write the image");
+        info!("wrote the visible trees to {}", path);
+    }
+    if let Some(path) = args.iter().find_map(|a| a.strip_prefix("--pgm=")) {
+        let file = File::create(path).expect("couldn't create the image file");
+        let image = heat_levels(&scores, 256).map(|level| *level as u8);
+        write_pgm(BufWriter::new(file), &image).expect("couldn't write the image");
+        info!("wrote the scenic scores to {}", path);
+    }
+}
 
-    let visible: i32 = visibility.iter().sum();
-    println!("# of visible trees: {}", visible);
+/// Looks back the way that a scan in `direction` came from, in a single
+/// scan. Trees that are taller than every tree behind them are marked in
+/// `visible`, and each tree's score in `scores` is multiplied by how many
+/// trees it can see before the view is blocked by one that is at least as
+/// tall, or reaches the edge of the map.
+///
+/// Each line keeps a stack of the trees that could still block the view of
+/// the trees after them; their heights only go down towards the top of the
+/// stack, since a tree hides everything shorter behind it. Every tree is
+/// pushed and popped at most once, so this is linear in the size of the map.
+fn look_back(
+    map: &Grid<u8>,
+    direction: Direction,
+    visible: &mut Grid<bool>,
+    scores: &mut Grid<usize>,
+) {
+    // the height of each possible blocker, and how many steps into the line
+    // it is.
+    let mut blockers: Vec<(u8, usize)> = Vec::with_capacity(map.width().max(map.height()));
+    let mut steps = 0;
+    scan_map(map, direction, |pos, last, cur| {
+        if last.is_none() {
+            blockers.clear();
+            steps = 0;
+        }
+        while blockers.last().is_some_and(|(height, _)| *height < cur) {
+            blockers.pop();
+        }
+        match blockers.last() {
+            Some((_, blocker)) => scores[pos] *= steps - blocker,
+            None => {
+                visible[pos] = true;
+                scores[pos] *= steps;
+            }
+        }
+        blockers.push((cur, steps));
+        steps += 1;
+        cur
+    });
 }
 
-enum Direction {
-    Left,
-    Right,
-    Down,
-    Up,
+/// Which trees can be seen from outside of the map, by looking along a row
+/// or column from one of the edges, and the scenic score of every tree: the
+/// product of its viewing distances in all four directions. Both come out of
+/// a single scan of the map in each direction.
+fn survey(map: &Grid<u8>) -> (Grid<bool>, Grid<usize>) {
+    let mut visible = Grid::filled(map.width(), map.height(), false);
+    let mut scores = Grid::filled(map.width(), map.height(), 1);
+    for dir in Direction::CARDINAL {
+        look_back(map, dir, &mut visible, &mut scores);
+    }
+    (visible, scores)
 }
-type Map = (usize, Vec<Vec<u8>>);
 
-fn scan_map<F>(map: &Map, direction: Direction, mut cb: F)
+/// The tree with the highest scenic score, and its score.
+fn best_scenic_spot(scores: &Grid<usize>) -> Option<(Pos, usize)> {
+    scores
+        .positions()
+        .map(|pos| (pos, scores[pos]))
+        .max_by_key(|(_, score)| *score)
+}
+
+/// The trees that can be seen from the top of the tree at `from`, looking
+/// out in each of the given directions. Each view takes in every tree up to
+/// and including the first one that is at least as tall.
+fn seen_from(map: &Grid<u8>, from: Pos, directions: &[Direction]) -> Vec<Pos> {
+    let mut seen = vec![];
+    for dir in directions {
+        for pos in map.ray(from, *dir) {
+            seen.push(pos);
+            if map[pos] >= map[from] {
+                break;
+            }
+        }
+    }
+    seen
+}
+
+/// The trees that can see the tree at `target` from their tops, looking in
+/// from each of the given directions. A tree can see it when every tree in
+/// between is shorter than the one doing the looking.
+fn seen_by(map: &Grid<u8>, target: Pos, directions: &[Direction]) -> Vec<Pos> {
+    let mut seen = vec![];
+    for dir in directions {
+        let mut tallest = None;
+        for pos in map.ray(target, *dir) {
+            if tallest.map_or(true, |t| map[pos] > t) {
+                seen.push(pos);
+            }
+            tallest = tallest.max(Some(map[pos]));
+        }
+    }
+    seen
+}
+
+fn parse_pos(text: &str) -> Option<Pos> {
+    let (row, col) = text.split_once(',')?;
+    Some(Pos::new(row.trim().parse().ok()?, col.trim().parse().ok()?))
+}
+
+const BOLD_GREEN: &str = "\x1b[1;32m";
+const DIM: &str = "\x1b[2m";
+const RESET: &str = "\x1b[0m";
+
+/// Draws the map with the visible trees picked out. With `color` the
+/// hidden trees are dimmed, otherwise they're replaced with a `.`.
+fn render_visibility(map: &Grid<u8>, visible: &Grid<bool>, color: bool) -> String {
+    let mut out = String::new();
+    for pos in map.positions() {
+        let height = match map[pos] {
+            h @ 0..=9 => (b'0' + h) as char,
+            _ => '#',
+        };
+        match (visible[pos], color) {
+            (true, true) => out.push_str(&format!("{}{}{}", BOLD_GREEN, height, RESET)),
+            (false, true) => out.push_str(&format!("{}{}{}", DIM, height, RESET)),
+            (true, false) => out.push(height),
+            (false, false) => out.push('.'),
+        }
+        if pos.col == map.width() - 1 {
+            out.push('\n');
+        }
+    }
+    out
+}
+
+/// Squeezes the scores into `0..levels`. They're spread out on a log
+/// scale, since a handful of trees score far higher than all the rest.
+fn heat_levels(scores: &Grid<usize>, levels: usize) -> Grid<usize> {
+    let max = scores.values().copied().max().unwrap_or(0);
+    if max == 0 {
+        return scores.map(|_| 0);
+    }
+    let top = (max as f64).ln_1p();
+    scores.map(|score| ((*score as f64).ln_1p() / top * (levels - 1) as f64).round() as usize)
+}
+
+const HEAT_RAMP: &[u8] = b" .:-=+*#%@";
+/// xterm colors going from black, through red, to yellow.
+const HEAT_COLORS: [u8; 10] = [16, 52, 88, 124, 160, 196, 202, 208, 214, 226];
+
+/// Draws the scenic scores as a heatmap, with one character per tree.
+fn render_heatmap(scores: &Grid<usize>, color: bool) -> String {
+    let levels = heat_levels(scores, HEAT_RAMP.len());
+    let mut out = String::new();
+    for pos in levels.positions() {
+        let level = levels[pos];
+        let shade = HEAT_RAMP[level] as char;
+        if color {
+            out.push_str(&format!("\x1b[38;5;{}m{}", HEAT_COLORS[level], shade));
+        } else {
+            out.push(shade);
+        }
+        if pos.col == levels.width() - 1 {
+            if color {
+                out.push_str(RESET);
+            }
+            out.push('\n');
+        }
+    }
+    out
+}
+
+/// One pixel per tree, green if it's visible and gray if it isn't. Taller
+/// trees are brighter.
+fn visibility_image(map: &Grid<u8>, visible: &Grid<bool>) -> Grid<[u8; 3]> {
+    let mut image = map.map(|_| [0; 3]);
+    for pos in map.positions() {
+        let height = map[pos].min(9);
+        image[pos] = if visible[pos] {
+            [0, 100 + height * 15, 0]
+        } else {
+            let gray = 40 + height * 10;
+            [gray, gray, gray]
+        };
+    }
+    image
+}
+
+/// Walks over every line of the map in the given direction, starting from
+/// the opposite edge. For each tree, `cb` is given its position, whatever
+/// `cb` returned for the tree before it on the same line, and its height.
+fn scan_map<F>(map: &Grid<u8>, direction: Direction, mut cb: F)
 where
-    F: FnMut((usize, usize), Option<u8>, u8) -> u8,
+    F: FnMut(Pos, Option<u8>, u8) -> u8,
 {
-    let width = map.0;
-    let (flip_scan, flip_access) = match direction {
-        Direction::Right => (false, false),
-        Direction::Down => (false, true),
-        Direction::Up => (true, true),
-        Direction::Left => (true, false),
-    };
-    let indexes = if flip_access {
-        |a, b| (b, a)
-    } else {
-        |a, b| (a, b)
-    };
-    for row in 0..map.0 {
+    for line in map.lines(direction) {
         let mut cur = None;
-        let scan: Box<dyn Iterator<Item = usize>> = if flip_scan {
-            Box::new((0..width).into_iter().rev())
-        } else {
-            Box::new((0..width).into_iter())
+        for scanned in line {
+            let visiting = map[scanned];
+            trace!(
+                "scanned row: {}, col: {}, last: {:?}, cur: {}",
+                scanned.row,
+                scanned.col,
+                cur,
+                visiting
+            );
+            cur = Some(cb(scanned, cur, visiting));
+        }
+    }
+}
+
+/// How the tree heights are written down in a map.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum MapFormat {
+    /// One digit per tree, as in the puzzle.
+    Digits,
+    /// Whole numbers from 0 to 255, with whitespace in between them.
+    Separated,
+}
+
+/// Lines and columns are counted from 1, like an editor would.
+#[derive(Error, Debug, PartialEq)]
+enum MapError {
+    #[error("line {line}, column {column}: {found:?} is not a tree height")]
+    NotAHeight {
+        line: usize,
+        column: usize,
+        found: String,
+    },
+    #[error("line {line}, column {column}: {found} is taller than 255")]
+    TooTall {
+        line: usize,
+        column: usize,
+        found: String,
+    },
+    #[error("line {line} has {found} trees, but the lines before it have {expected}")]
+    Ragged {
+        line: usize,
+        expected: usize,
+        found: usize,
+    },
+}
+
+/// Reads a map of tree heights. Blank lines are skipped, and lines may end
+/// with either `\n` or `\r\n`. Will panic on IO error.
+fn read_tree_map(from: &str, format: MapFormat) -> Result<Grid<u8>, MapError> {
+    let text = fs::read_to_string(from).expect("could not read the map");
+    parse_tree_map(&text, format)
+}
+
+fn parse_tree_map(text: &str, format: MapFormat) -> Result<Grid<u8>, MapError> {
+    let mut rows: Vec<Vec<u8>> = vec![];
+    for (i, line) in text.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let row = match format {
+            MapFormat::Digits => parse_digits(line, i + 1)?,
+            MapFormat::Separated => parse_separated(line, i + 1)?,
         };
-        for scanned in scan {
-            println!("scanned {:?}", scanned);
-            let (x, y) = indexes(row, scanned);
-            let visiting = map.1[x][y];
-            cur = Some(cb((y, x), cur, visiting));
+        if let Some(first) = rows.first() {
+            if first.len() != row.len() {
+                return Err(MapError::Ragged {
+                    line: i + 1,
+                    expected: first.len(),
+                    found: row.len(),
+                });
+            }
         }
+        rows.push(row);
     }
+    Ok(Grid::from_rows(rows).expect("the rows were checked to be the same length"))
+}
+
+fn parse_digits(line: &str, line_number: usize) -> Result<Vec<u8>, MapError> {
+    line.chars()
+        .enumerate()
+        .map(|(i, c)| match c.to_digit(10) {
+            Some(d) => Ok(d as u8),
+            None => Err(MapError::NotAHeight {
+                line: line_number,
+                column: i + 1,
+                found: c.to_string(),
+            }),
+        })
+        .collect()
 }
 
-fn read_tree_map(from: &str) -> (usize, Vec<Vec<u8>>) {
-    let f = File::open(from).unwrap();
-    let br = BufReader::new(f);
-    let mut map: Vec<Vec<u8>> = vec![];
-    for line in br.lines() {
-        map.push(line.unwrap().bytes().map(|b| b - b'0').collect());
+fn parse_separated(line: &str, line_number: usize) -> Result<Vec<u8>, MapError> {
+    let mut heights = vec![];
+    let mut rest = line;
+    while let Some(start) = rest.find(|c: char| !c.is_whitespace()) {
+        let column = line.len() - rest.len() + start + 1;
+        let token = &rest[start..];
+        let end = token.find(char::is_whitespace).unwrap_or(token.len());
+        let (token, after) = token.split_at(end);
+        if !token.chars().all(|c| c.is_ascii_digit()) {
+            return Err(MapError::NotAHeight {
+                line: line_number,
+                column,
+                found: token.to_string(),
+            });
+        }
+        let height = token.parse().map_err(|_| MapError::TooTall {
+            line: line_number,
+            column,
+            found: token.to_string(),
+        })?;
+        heights.push(height);
+        rest = after;
+    }
+    Ok(heights)
+}
+
+/// A `width` by `height` map of made up tree heights, for benchmarking and
+/// testing. The same seed always gives the same map.
+fn synthetic_map(width: usize, height: usize, seed: u64) -> Grid<u8> {
+    let mut rng = XorShift::new(seed);
+    let rows = (0..height)
+        .map(|_| (&mut rng).take(width).map(|n| (n % 10) as u8).collect())
+        .collect();
+    Grid::from_rows(rows).unwrap()
+}
+
+fn bench(size: usize) {
+    let started = Instant::now();
+    let map = synthetic_map(size, size, 2022);
+    println!("made a {0}x{0} map in {1:?}", size, started.elapsed());
+
+    let started = Instant::now();
+    let (visible, scores) = survey(&map);
+    println!("surveyed the map in {:?}", started.elapsed());
+    println!(
+        "{} visible trees, best scenic spot {:?}",
+        visible.values().filter(|v| **v).count(),
+        best_scenic_spot(&scores)
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use aoc::grid::{Direction, Grid, Pos};
+
+    use crate::{
+        best_scenic_spot, heat_levels, look_back, parse_pos, parse_tree_map, render_heatmap,
+        render_visibility, seen_by, seen_from, survey, synthetic_map, visibility_image, MapError,
+        MapFormat,
+    };
+
+    fn example() -> Grid<u8> {
+        parse_tree_map("30373\n25512\n65332\n33549\n35390\n", MapFormat::Digits).unwrap()
+    }
+
+    #[test]
+    fn parsing_maps() {
+        let map = example();
+        assert_eq!(map.width(), 5);
+        assert_eq!(map.row(2), [6, 5, 3, 3, 2]);
+
+        let crlf = parse_tree_map(
+            "30373\r\n25512\r\n65332\r\n33549\r\n35390\r\n\r\n",
+            MapFormat::Digits,
+        );
+        assert_eq!(crlf, Ok(map));
+
+        assert_eq!(
+            parse_tree_map("123\n4x6\n", MapFormat::Digits),
+            Err(MapError::NotAHeight {
+                line: 2,
+                column: 2,
+                found: "x".to_string()
+            })
+        );
+        assert_eq!(
+            parse_tree_map("123\n\n45\n", MapFormat::Digits),
+            Err(MapError::Ragged {
+                line: 3,
+                expected: 3,
+                found: 2
+            })
+        );
+    }
+
+    #[test]
+    fn parsing_separated_maps() {
+        let map = parse_tree_map("10  200 3\r\n\t4 5   60\n", MapFormat::Separated).unwrap();
+        assert_eq!(map.row(0), [10, 200, 3]);
+        assert_eq!(map.row(1), [4, 5, 60]);
+
+        assert_eq!(
+            parse_tree_map("1 2 3\n4 -5 6", MapFormat::Separated),
+            Err(MapError::NotAHeight {
+                line: 2,
+                column: 3,
+                found: "-5".to_string()
+            })
+        );
+        assert_eq!(
+            parse_tree_map("1 256", MapFormat::Separated),
+            Err(MapError::TooTall {
+                line: 1,
+                column: 3,
+                found: "256".to_string()
+            })
+        );
     }
 
-    let y_height = map.len();
-    let x_heights: HashSet<usize> = map.iter().map(|r| r.len()).collect();
-    if x_heights.len() != 1 || !x_heights.contains(&y_height) {
-        panic!("The map did not have the sime height and width.");
+    #[test]
+    fn visible_trees() {
+        let visible = survey(&example()).0;
+        assert_eq!(visible.values().filter(|v| **v).count(), 21);
+
+        let interior: Vec<Vec<bool>> = (1..4).map(|row| visible.row(row)[1..4].to_vec()).collect();
+        assert_eq!(
+            interior,
+            vec![
+                vec![true, true, false],
+                vec![true, false, true],
+                vec![false, true, false],
+            ]
+        );
+        for row in [0, 4] {
+            assert!(
+                visible.row(row).iter().all(|v| *v),
+                "row {} is an edge",
+                row
+            );
+        }
+        for col in [0, 4] {
+            assert!(visible.column(col).all(|v| *v), "col {} is an edge", col);
+        }
+    }
+
+    #[test]
+    fn rendering_visibility() {
+        let map = example();
+        let visible = survey(&map).0;
+        assert_eq!(
+            render_visibility(&map, &visible, false),
+            "30373\n255.2\n65.32\n3.5.9\n35390\n"
+        );
+        let colored = render_visibility(&map, &visible, true);
+        assert!(colored.starts_with("\x1b[1;32m3\x1b[0m"));
+        assert_eq!(colored.lines().count(), 5);
+
+        let image = visibility_image(&map, &visible);
+        assert_eq!(image[Pos::new(0, 0)], [0, 145, 0]);
+        assert_eq!(image[Pos::new(1, 3)], [50, 50, 50]);
+    }
+
+    #[test]
+    fn rendering_heatmap() {
+        let scores = survey(&example()).1;
+        let levels = heat_levels(&scores, 256);
+        assert_eq!(levels[Pos::new(3, 2)], 255, "the best tree is the hottest");
+        assert_eq!(levels[Pos::new(0, 0)], 0);
+
+        let heatmap = render_heatmap(&scores, false);
+        let rows: Vec<&str> = heatmap.lines().collect();
+        assert_eq!(rows[0], "     ");
+        assert_eq!(&rows[3][2..3], "@");
+        assert!(render_heatmap(&scores, true).contains("\x1b[38;5;226m@"));
+
+        let flat = Grid::filled(2, 2, 0);
+        assert_eq!(render_heatmap(&flat, false), "  \n  \n");
+    }
+
+    #[test]
+    fn visibility_on_a_rectangle() {
+        let map =
+            Grid::from_rows(vec![vec![3, 3, 3, 3], vec![3, 1, 4, 3], vec![3, 3, 3, 3]]).unwrap();
+        let visible = survey(&map).0;
+        assert!(!visible[Pos::new(1, 1)]);
+        assert!(visible[Pos::new(1, 2)]);
+        assert_eq!(visible.values().filter(|v| **v).count(), 11);
+    }
+
+    #[test]
+    fn viewing_distance() {
+        let map = example();
+        // with every score starting at 1, one scan leaves just the viewing
+        // distances in that direction.
+        let mut visible = Grid::filled(5, 5, false);
+        let mut left = Grid::filled(5, 5, 1);
+        // scanning right, so looking left
+        look_back(&map, Direction::Right, &mut visible, &mut left);
+        assert_eq!(left.row(1), [0, 1, 1, 1, 2]);
+        assert_eq!(visible.row(1), [true, true, false, false, false]);
+        let mut down = Grid::filled(5, 5, 1);
+        // scanning up, so looking down
+        look_back(&map, Direction::Up, &mut visible, &mut down);
+        assert_eq!(down[Pos::new(1, 2)], 2);
+        assert_eq!(down[Pos::new(3, 2)], 1);
+    }
+
+    #[test]
+    fn scenic_score() {
+        let map = example();
+        let scores = survey(&map).1;
+        assert_eq!(scores[Pos::new(1, 2)], 4);
+        assert_eq!(scores[Pos::new(3, 2)], 8);
+        assert_eq!(
+            scores[Pos::new(0, 0)],
+            0,
+            "trees on the edge see nothing one way"
+        );
+        assert_eq!(best_scenic_spot(&scores), Some((Pos::new(3, 2), 8)));
+    }
+
+    #[test]
+    fn line_of_sight_queries() {
+        let map = example();
+        let p = Pos::new;
+        let mut seen = seen_from(&map, p(3, 2), &Direction::CARDINAL);
+        seen.sort();
+        assert_eq!(
+            seen,
+            vec![
+                p(1, 2),
+                p(2, 2),
+                p(3, 0),
+                p(3, 1),
+                p(3, 3),
+                p(3, 4),
+                p(4, 2)
+            ]
+        );
+
+        // the 3 in the middle only sees over the 1 up and to its right.
+        let mut diagonal = seen_from(&map, p(2, 2), &Direction::DIAGONAL);
+        diagonal.sort();
+        assert_eq!(diagonal, vec![p(0, 4), p(1, 1), p(1, 3), p(3, 1), p(3, 3)]);
+
+        let mut by = seen_by(&map, p(0, 0), &Direction::CARDINAL);
+        by.sort();
+        assert_eq!(by, vec![p(0, 1), p(0, 2), p(0, 3), p(1, 0), p(2, 0)]);
+
+        assert_eq!(parse_pos("3, 2"), Some(p(3, 2)));
+        assert_eq!(parse_pos("3"), None);
+    }
+
+    #[test]
+    fn seeing_and_being_seen_agree() {
+        let map = synthetic_map(13, 11, 40);
+        for from in map.positions() {
+            for to in seen_from(&map, from, &Direction::ALL) {
+                assert!(seen_by(&map, to, &Direction::ALL).contains(&from));
+            }
+            for by in seen_by(&map, from, &Direction::ALL) {
+                assert!(seen_from(&map, by, &Direction::ALL).contains(&from));
+            }
+        }
+    }
+
+    /// Walks out from every tree, one tree at a time.
+    fn naive_viewing_distance(map: &Grid<u8>, pos: Pos, direction: Direction) -> (bool, usize) {
+        let mut seen = 0;
+        for other in map.ray(pos, direction) {
+            seen += 1;
+            if map[other] >= map[pos] {
+                return (false, seen);
+            }
+        }
+        (true, seen)
+    }
+
+    #[test]
+    fn matches_walking_out_from_each_tree() {
+        let map = synthetic_map(37, 23, 8);
+        let (visible, scores) = survey(&map);
+        for pos in map.positions() {
+            let views: Vec<(bool, usize)> = Direction::CARDINAL
+                .iter()
+                .map(|d| naive_viewing_distance(&map, pos, *d))
+                .collect();
+            assert_eq!(visible[pos], views.iter().any(|(v, _)| *v), "{:?}", pos);
+            assert_eq!(
+                scores[pos],
+                views.iter().map(|(_, d)| d).product::<usize>(),
+                "{:?}",
+                pos
+            );
+        }
     }
-    (y_height, map)
 }
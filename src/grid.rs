@@ -0,0 +1,403 @@
+use std::{
+    iter::once,
+    ops::{Index, IndexMut},
+};
+
+/// A position in a grid. Rows count down from the top, and columns count
+/// across from the left.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Pos {
+    pub row: usize,
+    pub col: usize,
+}
+
+impl Pos {
+    pub fn new(row: usize, col: usize) -> Self {
+        Self { row, col }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+    UpLeft,
+    UpRight,
+    DownLeft,
+    DownRight,
+}
+
+impl Direction {
+    pub const CARDINAL: [Direction; 4] = [
+        Direction::Up,
+        Direction::Down,
+        Direction::Left,
+        Direction::Right,
+    ];
+
+    pub const DIAGONAL: [Direction; 4] = [
+        Direction::UpLeft,
+        Direction::UpRight,
+        Direction::DownLeft,
+        Direction::DownRight,
+    ];
+
+    pub const ALL: [Direction; 8] = [
+        Direction::Up,
+        Direction::Down,
+        Direction::Left,
+        Direction::Right,
+        Direction::UpLeft,
+        Direction::UpRight,
+        Direction::DownLeft,
+        Direction::DownRight,
+    ];
+
+    pub fn opposite(&self) -> Self {
+        match self {
+            Self::Up => Self::Down,
+            Self::Down => Self::Up,
+            Self::Left => Self::Right,
+            Self::Right => Self::Left,
+            Self::UpLeft => Self::DownRight,
+            Self::UpRight => Self::DownLeft,
+            Self::DownLeft => Self::UpRight,
+            Self::DownRight => Self::UpLeft,
+        }
+    }
+
+    /// How far one step moves, as `(rows, columns)`.
+    pub fn delta(&self) -> (isize, isize) {
+        match self {
+            Self::Up => (-1, 0),
+            Self::Down => (1, 0),
+            Self::Left => (0, -1),
+            Self::Right => (0, 1),
+            Self::UpLeft => (-1, -1),
+            Self::UpRight => (-1, 1),
+            Self::DownLeft => (1, -1),
+            Self::DownRight => (1, 1),
+        }
+    }
+}
+
+/// A rectangular grid of values, stored row by row.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+}
+
+impl<T> Grid<T> {
+    /// Builds a grid out of its rows. Gives back `None` if the rows aren't
+    /// all the same length.
+    pub fn from_rows(rows: Vec<Vec<T>>) -> Option<Self> {
+        let height = rows.len();
+        let width = rows.first().map(|r| r.len()).unwrap_or(0);
+        if rows.iter().any(|r| r.len() != width) {
+            return None;
+        }
+        Some(Self {
+            width,
+            height,
+            cells: rows.into_iter().flatten().collect(),
+        })
+    }
+
+    pub fn filled(width: usize, height: usize, value: T) -> Self
+    where
+        T: Clone,
+    {
+        Self {
+            width,
+            height,
+            cells: vec![value; width * height],
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn contains(&self, pos: Pos) -> bool {
+        pos.row < self.height && pos.col < self.width
+    }
+
+    pub fn get(&self, pos: Pos) -> Option<&T> {
+        self.contains(pos).then(|| &self.cells[self.offset(pos)])
+    }
+
+    pub fn get_mut(&mut self, pos: Pos) -> Option<&mut T> {
+        if !self.contains(pos) {
+            return None;
+        }
+        let offset = self.offset(pos);
+        Some(&mut self.cells[offset])
+    }
+
+    fn offset(&self, pos: Pos) -> usize {
+        pos.row * self.width + pos.col
+    }
+
+    /// Every position in the grid, row by row.
+    pub fn positions(&self) -> impl Iterator<Item = Pos> {
+        let width = self.width;
+        (0..self.height).flat_map(move |row| (0..width).map(move |col| Pos::new(row, col)))
+    }
+
+    /// Every value in the grid, row by row.
+    pub fn values(&self) -> impl Iterator<Item = &T> {
+        self.cells.iter()
+    }
+
+    pub fn values_mut(&mut self) -> impl Iterator<Item = &mut T> {
+        self.cells.iter_mut()
+    }
+
+    /// A grid of the same shape, with `f` applied to every value.
+    pub fn map<U, F: FnMut(&T) -> U>(&self, f: F) -> Grid<U> {
+        Grid {
+            width: self.width,
+            height: self.height,
+            cells: self.cells.iter().map(f).collect(),
+        }
+    }
+
+    pub fn row(&self, row: usize) -> &[T] {
+        &self.cells[row * self.width..(row + 1) * self.width]
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        (0..self.height).map(|row| self.row(row))
+    }
+
+    pub fn column(&self, col: usize) -> impl Iterator<Item = &T> {
+        assert!(col < self.width, "column {} is out of bounds", col);
+        self.cells.iter().skip(col).step_by(self.width)
+    }
+
+    pub fn columns(&self) -> impl Iterator<Item = impl Iterator<Item = &T>> {
+        (0..self.width).map(|col| self.column(col))
+    }
+
+    /// The position one step away in the given direction, if it's still
+    /// inside the grid.
+    pub fn step(&self, Pos { row, col }: Pos, direction: Direction) -> Option<Pos> {
+        let (rows, cols) = direction.delta();
+        let next = Pos::new(row.checked_add_signed(rows)?, col.checked_add_signed(cols)?);
+        self.contains(next).then_some(next)
+    }
+
+    /// The positions directly above, below, left and right of `pos` that are
+    /// inside the grid.
+    pub fn neighbors(&self, pos: Pos) -> impl Iterator<Item = Pos> + '_ {
+        Direction::CARDINAL
+            .into_iter()
+            .filter_map(move |d| self.step(pos, d))
+    }
+
+    /// Walks from `from` in a straight line until it leaves the grid. The
+    /// starting position itself is not included.
+    pub fn ray(&self, from: Pos, direction: Direction) -> Ray<'_, T> {
+        Ray {
+            grid: self,
+            at: from,
+            direction,
+        }
+    }
+
+    /// The positions along the edge of the grid that a scan heading in
+    /// `direction` would start from; the left column when heading right,
+    /// the bottom row when heading up, and so on. Diagonal scans start from
+    /// two edges, such as the top row and the left column when heading down
+    /// and to the right.
+    pub fn edge(&self, direction: Direction) -> Vec<Pos> {
+        if self.width == 0 || self.height == 0 {
+            return vec![];
+        }
+        let (rows, cols) = direction.delta();
+        let start_row = match rows {
+            1 => Some(0),
+            -1 => Some(self.height - 1),
+            _ => None,
+        };
+        let start_col = match cols {
+            1 => Some(0),
+            -1 => Some(self.width - 1),
+            _ => None,
+        };
+        let mut starts = vec![];
+        if let Some(row) = start_row {
+            starts.extend((0..self.width).map(|col| Pos::new(row, col)));
+        }
+        if let Some(col) = start_col {
+            starts.extend(
+                (0..self.height)
+                    .filter(|row| Some(*row) != start_row)
+                    .map(|row| Pos::new(row, col)),
+            );
+        }
+        starts
+    }
+
+    /// Every line through the grid in the given direction, each one starting
+    /// from the edge. Between them they cover every position exactly once.
+    pub fn lines(
+        &self,
+        direction: Direction,
+    ) -> impl Iterator<Item = impl Iterator<Item = Pos> + '_> {
+        self.edge(direction)
+            .into_iter()
+            .map(move |start| once(start).chain(self.ray(start, direction)))
+    }
+}
+
+impl<T> Index<Pos> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, pos: Pos) -> &T {
+        self.get(pos)
+            .unwrap_or_else(|| panic!("{:?} is outside of the grid", pos))
+    }
+}
+
+impl<T> IndexMut<Pos> for Grid<T> {
+    fn index_mut(&mut self, pos: Pos) -> &mut T {
+        self.get_mut(pos)
+            .unwrap_or_else(|| panic!("{:?} is outside of the grid", pos))
+    }
+}
+
+pub struct Ray<'a, T> {
+    grid: &'a Grid<T>,
+    at: Pos,
+    direction: Direction,
+}
+
+impl<'a, T> Iterator for Ray<'a, T> {
+    type Item = Pos;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.at = self.grid.step(self.at, self.direction)?;
+        Some(self.at)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> Grid<u8> {
+        Grid::from_rows(vec![vec![1, 2, 3], vec![4, 5, 6]]).unwrap()
+    }
+
+    #[test]
+    fn building_grids() {
+        let grid = sample();
+        assert_eq!(grid.width(), 3);
+        assert_eq!(grid.height(), 2);
+        assert_eq!(grid[Pos::new(0, 2)], 3);
+        assert_eq!(grid[Pos::new(1, 0)], 4);
+        assert_eq!(grid.get(Pos::new(0, 3)), None);
+        assert_eq!(grid.get(Pos::new(2, 0)), None);
+
+        assert!(Grid::from_rows(vec![vec![1, 2], vec![3]]).is_none());
+
+        let empty: Grid<u8> = Grid::from_rows(vec![]).unwrap();
+        assert_eq!(empty.positions().count(), 0);
+        assert!(empty.edge(Direction::Up).is_empty());
+    }
+
+    #[test]
+    fn rows_and_columns() {
+        let grid = sample();
+        assert_eq!(grid.rows().collect::<Vec<_>>(), vec![[1, 2, 3], [4, 5, 6]]);
+        let columns: Vec<Vec<u8>> = grid.columns().map(|c| c.copied().collect()).collect();
+        assert_eq!(columns, vec![vec![1, 4], vec![2, 5], vec![3, 6]]);
+        let doubled = grid.map(|v| *v as u32 * 2);
+        assert_eq!(doubled.row(1), [8, 10, 12]);
+        let positions: Vec<(usize, usize)> = grid.positions().map(|p| (p.row, p.col)).collect();
+        assert_eq!(
+            positions,
+            vec![(0, 0), (0, 1), (0, 2), (1, 0), (1, 1), (1, 2)]
+        );
+    }
+
+    #[test]
+    fn neighbors_and_rays() {
+        let grid = sample();
+        let p = Pos::new;
+        let mut neighbors: Vec<Pos> = grid.neighbors(p(0, 1)).collect();
+        neighbors.sort();
+        assert_eq!(neighbors, vec![p(0, 0), p(0, 2), p(1, 1)]);
+
+        assert_eq!(grid.step(p(0, 0), Direction::Up), None);
+        assert_eq!(grid.step(p(0, 0), Direction::Down), Some(p(1, 0)));
+
+        assert_eq!(
+            grid.ray(p(1, 0), Direction::Right).collect::<Vec<_>>(),
+            vec![p(1, 1), p(1, 2)]
+        );
+        assert_eq!(grid.ray(p(1, 2), Direction::Right).count(), 0);
+        assert_eq!(
+            grid.ray(p(1, 1), Direction::Up).collect::<Vec<_>>(),
+            vec![p(0, 1)]
+        );
+        assert_eq!(grid.edge(Direction::Up), vec![p(1, 0), p(1, 1), p(1, 2)]);
+        assert_eq!(grid.edge(Direction::Left), vec![p(0, 2), p(1, 2)]);
+    }
+
+    #[test]
+    fn diagonals() {
+        let grid = sample();
+        let p = Pos::new;
+        assert_eq!(grid.step(p(0, 0), Direction::DownRight), Some(p(1, 1)));
+        assert_eq!(grid.step(p(0, 0), Direction::UpRight), None);
+        assert_eq!(
+            grid.ray(p(1, 2), Direction::UpLeft).collect::<Vec<_>>(),
+            vec![p(0, 1)]
+        );
+        assert_eq!(
+            grid.edge(Direction::DownRight),
+            vec![p(0, 0), p(0, 1), p(0, 2), p(1, 0)]
+        );
+        assert_eq!(
+            grid.edge(Direction::UpLeft),
+            vec![p(1, 0), p(1, 1), p(1, 2), p(0, 2)]
+        );
+        for d in Direction::ALL {
+            assert_eq!(d.opposite().opposite(), d);
+        }
+    }
+
+    #[test]
+    fn lines_cover_the_grid() {
+        let grid = sample();
+        for d in Direction::ALL {
+            let mut covered: Vec<Pos> = grid.lines(d).flatten().collect();
+            covered.sort();
+            assert_eq!(covered, grid.positions().collect::<Vec<_>>(), "{:?}", d);
+        }
+        let lines: Vec<Vec<Pos>> = grid
+            .lines(Direction::DownLeft)
+            .map(|l| l.collect())
+            .collect();
+        let p = Pos::new;
+        assert_eq!(
+            lines,
+            vec![
+                vec![p(0, 0)],
+                vec![p(0, 1), p(1, 0)],
+                vec![p(0, 2), p(1, 1)],
+                vec![p(1, 2)]
+            ]
+        );
+    }
+}
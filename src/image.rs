@@ -0,0 +1,44 @@
+use std::io::{self, Write};
+
+use crate::grid::Grid;
+
+/// Writes a grid of gray levels as a binary PGM image, with one pixel per
+/// cell. 0 is black and 255 is white.
+pub fn write_pgm<W: Write>(mut out: W, grid: &Grid<u8>) -> io::Result<()> {
+    write!(out, "P5\n{} {}\n255\n", grid.width(), grid.height())?;
+    for row in grid.rows() {
+        out.write_all(row)?;
+    }
+    Ok(())
+}
+
+/// Writes a grid of `[red, green, blue]` colors as a binary PPM image, with
+/// one pixel per cell.
+pub fn write_ppm<W: Write>(mut out: W, grid: &Grid<[u8; 3]>) -> io::Result<()> {
+    write!(out, "P6\n{} {}\n255\n", grid.width(), grid.height())?;
+    for row in grid.rows() {
+        out.write_all(row.concat().as_slice())?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn writing_pgm() {
+        let grid = Grid::from_rows(vec![vec![0, 128, 255], vec![1, 2, 3]]).unwrap();
+        let mut out = vec![];
+        write_pgm(&mut out, &grid).unwrap();
+        assert_eq!(out, b"P5\n3 2\n255\n\x00\x80\xff\x01\x02\x03");
+    }
+
+    #[test]
+    fn writing_ppm() {
+        let grid = Grid::from_rows(vec![vec![[255, 0, 0]], vec![[0, 0, 9]]]).unwrap();
+        let mut out = vec![];
+        write_ppm(&mut out, &grid).unwrap();
+        assert_eq!(out, b"P6\n1 2\n255\n\xff\x00\x00\x00\x00\x09");
+    }
+}
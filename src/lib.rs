@@ -1,2 +1,7 @@
 pub mod data;
+pub mod grid;
+pub mod image;
+pub mod log;
+pub mod marker;
+pub mod rng;
 pub mod shell_parse;
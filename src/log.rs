@@ -0,0 +1,128 @@
+use std::{
+    env,
+    sync::atomic::{AtomicU8, Ordering},
+};
+
+/// How much the solvers should print, on top of their answers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Verbosity {
+    /// Only the answers.
+    Quiet = 0,
+    /// A little bit about what is going on.
+    Info = 1,
+    /// Everything, including dumps of intermediate state.
+    Trace = 2,
+}
+
+static VERBOSITY: AtomicU8 = AtomicU8::new(Verbosity::Info as u8);
+
+impl Verbosity {
+    fn from_u8(level: u8) -> Self {
+        match level {
+            0 => Self::Quiet,
+            1 => Self::Info,
+            _ => Self::Trace,
+        }
+    }
+
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "quiet" => Some(Self::Quiet),
+            "info" => Some(Self::Info),
+            "trace" => Some(Self::Trace),
+            _ => None,
+        }
+    }
+
+    /// Picks the verbosity out of the command line. `-q`/`--quiet` and
+    /// `-v`/`--verbose` win over the `AOC_VERBOSITY` variable, which can be
+    /// `quiet`, `info` or `trace`.
+    pub fn from_args<I: Iterator<Item = String>>(args: I, var: Option<&str>) -> Self {
+        let mut verbosity = var.and_then(Self::parse).unwrap_or(Self::Info);
+        for arg in args {
+            match arg.as_str() {
+                "-q" | "--quiet" => verbosity = Self::Quiet,
+                "-v" | "--verbose" => verbosity = Self::Trace,
+                _ => {}
+            }
+        }
+        verbosity
+    }
+}
+
+/// Whether a message at the given level should be printed.
+pub fn enabled(level: Verbosity) -> bool {
+    level <= verbosity()
+}
+
+pub fn verbosity() -> Verbosity {
+    Verbosity::from_u8(VERBOSITY.load(Ordering::Relaxed))
+}
+
+pub fn set_verbosity(verbosity: Verbosity) {
+    VERBOSITY.store(verbosity as u8, Ordering::Relaxed);
+}
+
+/// Sets the verbosity from the program's own arguments and environment.
+/// Every day should call this first thing.
+pub fn init() {
+    let var = env::var("AOC_VERBOSITY").ok();
+    set_verbosity(Verbosity::from_args(env::args().skip(1), var.as_deref()));
+}
+
+/// Whether an argument is one of the verbosity flags, so that days which
+/// take other arguments can skip over them.
+pub fn is_flag(arg: &str) -> bool {
+    matches!(arg, "-q" | "--quiet" | "-v" | "--verbose")
+}
+
+/// Prints a line, unless running quietly.
+#[macro_export]
+macro_rules! info {
+    ($($arg:tt)*) => {
+        if $crate::log::enabled($crate::log::Verbosity::Info) {
+            println!($($arg)*);
+        }
+    };
+}
+
+/// Prints a line, but only when asked to be verbose.
+#[macro_export]
+macro_rules! trace {
+    ($($arg:tt)*) => {
+        if $crate::log::enabled($crate::log::Verbosity::Trace) {
+            println!($($arg)*);
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn from_args(args: &[&str], var: Option<&str>) -> Verbosity {
+        Verbosity::from_args(args.iter().map(|a| a.to_string()), var)
+    }
+
+    #[test]
+    fn verbosity_from_args() {
+        assert_eq!(from_args(&[], None), Verbosity::Info);
+        assert_eq!(from_args(&["-q"], None), Verbosity::Quiet);
+        assert_eq!(
+            from_args(&["--verbose", "input.txt"], None),
+            Verbosity::Trace
+        );
+        assert_eq!(from_args(&[], Some("trace")), Verbosity::Trace);
+        assert_eq!(from_args(&[], Some("nonsense")), Verbosity::Info);
+        assert_eq!(from_args(&["--quiet"], Some("trace")), Verbosity::Quiet);
+    }
+
+    #[test]
+    fn levels_are_ordered() {
+        assert!(Verbosity::Quiet < Verbosity::Info);
+        assert!(Verbosity::Info < Verbosity::Trace);
+        for level in [Verbosity::Quiet, Verbosity::Info, Verbosity::Trace] {
+            assert_eq!(Verbosity::from_u8(level as u8), level);
+        }
+    }
+}
@@ -0,0 +1,330 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    hash::Hash,
+    io::{self, ErrorKind, Read},
+};
+
+/// A run of items that are all different from each other.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Marker<T> {
+    /// How many items came before the marker.
+    pub start: usize,
+    /// The items in the marker, in order.
+    pub window: Vec<T>,
+}
+
+impl<T> Marker<T> {
+    /// How many items have been read once the marker is complete.
+    pub fn end(&self) -> usize {
+        self.start + self.window.len()
+    }
+}
+
+/// Keeps track of the last few items pushed into it, and whether they are
+/// all different. Each item remembers where it was last seen, so a push only
+/// has to look up the one item rather than compare the whole window.
+#[derive(Debug, Clone)]
+pub struct DistinctWindow<T> {
+    length: usize,
+    window: VecDeque<T>,
+    last_seen: HashMap<T, usize>,
+    /// Where the longest run of distinct items ending at the latest item
+    /// starts.
+    run_start: usize,
+    pushed: usize,
+}
+
+impl<T: Hash + Eq + Clone> DistinctWindow<T> {
+    pub fn new(length: usize) -> Self {
+        Self {
+            length,
+            window: VecDeque::with_capacity(length + 1),
+            last_seen: HashMap::new(),
+            run_start: 0,
+            pushed: 0,
+        }
+    }
+
+    /// Adds the next item, and says whether the last `length` items are now
+    /// all different.
+    pub fn push(&mut self, item: T) -> bool {
+        let index = self.pushed;
+        self.pushed += 1;
+        if let Some(last) = self.last_seen.insert(item.clone(), index) {
+            self.run_start = self.run_start.max(last + 1);
+        }
+        self.window.push_back(item);
+        if self.window.len() > self.length {
+            self.window.pop_front();
+        }
+        self.pushed - self.run_start >= self.length
+    }
+
+    /// How many items have been pushed so far.
+    pub fn pushed(&self) -> usize {
+        self.pushed
+    }
+
+    /// The window as a marker, whether or not its items are distinct.
+    pub fn marker(&self) -> Marker<T> {
+        Marker {
+            start: self.pushed - self.window.len(),
+            window: self.window.iter().cloned().collect(),
+        }
+    }
+}
+
+/// Finds the first `length` items in a row that are all different, in a
+/// single pass. A length of 0 never makes a marker.
+pub fn first_distinct<I>(items: I, length: usize) -> Option<Marker<I::Item>>
+where
+    I: IntoIterator,
+    I::Item: Hash + Eq + Clone,
+{
+    if length == 0 {
+        return None;
+    }
+    let mut window = DistinctWindow::new(length);
+    for item in items {
+        if window.push(item) {
+            return Some(window.marker());
+        }
+    }
+    None
+}
+
+/// Every run of `length` items that are all different, including ones that
+/// overlap, in the order that they end. There are none of length 0.
+pub fn all_distinct<I>(items: I, length: usize) -> impl Iterator<Item = Marker<I::Item>>
+where
+    I: IntoIterator,
+    I::Item: Hash + Eq + Clone,
+{
+    let mut window = DistinctWindow::new(length);
+    items
+        .into_iter()
+        .take_while(move |_| length > 0)
+        .filter_map(move |item| window.push(item).then(|| window.marker()))
+}
+
+/// A marker, and the items after it up to the next marker.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Segment<'a, T> {
+    /// Where the marker starts.
+    pub start: usize,
+    pub marker: &'a [T],
+    pub payload: &'a [T],
+}
+
+/// Splits `items` up at markers that don't overlap; after one marker is
+/// found, the search for the next one starts over from its end. Anything
+/// before the first marker is skipped.
+pub fn segments<T: Hash + Eq + Clone>(items: &[T], length: usize) -> Vec<Segment<'_, T>> {
+    let mut starts = vec![];
+    let mut from = 0;
+    while length > 0 && from < items.len() {
+        match first_distinct(&items[from..], length) {
+            Some(marker) => {
+                starts.push(from + marker.start);
+                from += marker.end();
+            }
+            None => break,
+        }
+    }
+    starts
+        .iter()
+        .enumerate()
+        .map(|(i, &start)| {
+            let end = starts.get(i + 1).copied().unwrap_or(items.len());
+            Segment {
+                start,
+                marker: &items[start..start + length],
+                payload: &items[start + length..end],
+            }
+        })
+        .collect()
+}
+
+const CHUNK_SIZE: usize = 8 * 1024;
+
+/// Reads bytes from `reader` a chunk at a time, looking for a marker of each
+/// of the given lengths. `found` is called with the length and end of each
+/// marker as soon as it's complete, and reading stops once they have all
+/// been found, so this can be pointed at input that never ends. Lengths of
+/// 0 are never found.
+pub fn stream_markers<R, F>(mut reader: R, lengths: &[usize], mut found: F) -> io::Result<()>
+where
+    R: Read,
+    F: FnMut(usize, usize),
+{
+    let mut windows: Vec<DistinctWindow<u8>> = lengths
+        .iter()
+        .filter(|length| **length > 0)
+        .map(|length| DistinctWindow::new(*length))
+        .collect();
+    let mut chunk = vec![0; CHUNK_SIZE];
+    while !windows.is_empty() {
+        let read = match reader.read(&mut chunk) {
+            Ok(0) => break,
+            Ok(read) => read,
+            Err(err) if err.kind() == ErrorKind::Interrupted => continue,
+            Err(err) => return Err(err),
+        };
+        for &b in &chunk[..read] {
+            windows.retain_mut(|window| {
+                if window.push(b) {
+                    found(window.length, window.pushed());
+                    false
+                } else {
+                    true
+                }
+            });
+            if windows.is_empty() {
+                break;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::XorShift;
+
+    #[test]
+    fn finding_the_first_marker() {
+        let marker = first_distinct("mjqjpqmgbljsphdztnvjfqwrcgsmlb".bytes(), 4).unwrap();
+        assert_eq!(marker.start, 3);
+        assert_eq!(marker.end(), 7);
+        assert_eq!(marker.window, b"jpqm");
+
+        let marker = first_distinct("abcd".chars(), 4).unwrap();
+        assert_eq!((marker.start, marker.window), (0, vec!['a', 'b', 'c', 'd']));
+
+        assert_eq!(first_distinct("aabbcc".bytes(), 3), None);
+        assert_eq!(first_distinct("ab".bytes(), 3), None);
+        assert_eq!(first_distinct("ab".bytes(), 0), None);
+    }
+
+    #[test]
+    fn marker_in_the_first_window() {
+        let marker = first_distinct("abcdabcd".bytes(), 4).unwrap();
+        assert_eq!((marker.start, marker.end()), (0, 4));
+        assert_eq!(marker.window, b"abcd");
+
+        let marker = first_distinct("abcdefghijklmnaaaa".bytes(), 14).unwrap();
+        assert_eq!((marker.start, marker.end()), (0, 14));
+
+        let first = all_distinct("abcabc".bytes(), 3).next().unwrap();
+        assert_eq!((first.start, first.window), (0, b"abc".to_vec()));
+        assert_eq!(
+            all_distinct("ab".bytes(), 2)
+                .map(|m| m.start)
+                .collect::<Vec<_>>(),
+            vec![0]
+        );
+
+        let mut found = vec![];
+        let stream = "abcdefghijklmnaaaa".as_bytes();
+        stream_markers(stream, &[4, 14], |length, end| found.push((length, end))).unwrap();
+        assert_eq!(found, vec![(4, 4), (14, 14)]);
+    }
+
+    #[test]
+    fn works_on_any_items() {
+        let words = ["red", "red", "blue", "green", "red"];
+        let marker = first_distinct(&words, 3).unwrap();
+        assert_eq!(marker.start, 1);
+        assert_eq!(marker.window, vec![&"red", &"blue", &"green"]);
+
+        let numbers = [1, 2, 1, 3, 2, 4];
+        assert_eq!(first_distinct(numbers, 3).map(|m| m.end()), Some(4));
+    }
+
+    #[test]
+    fn finding_every_marker() {
+        let starts: Vec<usize> = all_distinct("abcabba".bytes(), 3)
+            .map(|m| m.start)
+            .collect();
+        assert_eq!(starts, vec![0, 1, 2]);
+        let markers: Vec<Vec<u8>> = all_distinct("aabcdd".bytes(), 3)
+            .map(|m| m.window)
+            .collect();
+        assert_eq!(markers, vec![b"abc".to_vec(), b"bcd".to_vec()]);
+        assert_eq!(all_distinct("aaaa".bytes(), 2).count(), 0);
+        assert_eq!(all_distinct("abcd".bytes(), 0).count(), 0);
+    }
+
+    #[test]
+    fn splitting_into_segments() {
+        let found = segments(b"aaabcxxxdefyy", 3);
+        let parts: Vec<(usize, &[u8], &[u8])> = found
+            .iter()
+            .map(|s| (s.start, s.marker, s.payload))
+            .collect();
+        assert_eq!(
+            parts,
+            vec![(2, &b"abc"[..], &b"xx"[..]), (7, b"xde", b"fyy")]
+        );
+        assert!(segments(b"aaaa", 2).is_empty());
+        assert!(segments(b"abcd", 0).is_empty());
+    }
+
+    /// Hands out its bytes a few at a time, and then never runs out.
+    struct Trickle<'a> {
+        bytes: &'a [u8],
+    }
+
+    impl Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            if self.bytes.is_empty() {
+                buf[0] = b'a';
+                return Ok(1);
+            }
+            let n = self.bytes.len().min(buf.len()).min(3);
+            buf[..n].copy_from_slice(&self.bytes[..n]);
+            self.bytes = &self.bytes[n..];
+            Ok(n)
+        }
+    }
+
+    #[test]
+    fn streaming_markers() {
+        let mut found = vec![];
+        let stream = "mjqjpqmgbljsphdztnvjfqwrcgsmlb".as_bytes();
+        stream_markers(stream, &[4, 14], |length, end| found.push((length, end))).unwrap();
+        assert_eq!(found, vec![(4, 7), (14, 19)]);
+
+        let mut found = vec![];
+        let endless = Trickle {
+            bytes: b"bvwbjplbgvbhsrlpgdmjqwftvncz",
+        };
+        stream_markers(endless, &[14, 4], |length, end| found.push((length, end))).unwrap();
+        assert_eq!(found, vec![(4, 5), (14, 23)]);
+
+        let mut found = vec![];
+        stream_markers(&b"aaaa"[..], &[2, 0], |length, end| {
+            found.push((length, end))
+        })
+        .unwrap();
+        assert!(found.is_empty());
+    }
+
+    #[test]
+    fn matches_checking_every_window() {
+        let items: Vec<u64> = XorShift::new(17).take(2000).map(|n| n % 12).collect();
+        for length in 1..8 {
+            let naive = items.windows(length).position(|w| {
+                w.iter()
+                    .enumerate()
+                    .all(|(i, a)| w[i + 1..].iter().all(|b| a != b))
+            });
+            let found = first_distinct(items.iter().copied(), length);
+            assert_eq!(found.as_ref().map(|m| m.start), naive, "{}", length);
+            if let (Some(found), Some(start)) = (found, naive) {
+                assert_eq!(found.window, items[start..start + length]);
+            }
+        }
+    }
+}
@@ -0,0 +1,39 @@
+/// A small xorshift generator, for making up data to test and benchmark
+/// with. It's nowhere near random enough for anything else, but the same
+/// seed always gives the same numbers.
+#[derive(Debug, Clone)]
+pub struct XorShift {
+    state: u64,
+}
+
+impl XorShift {
+    pub fn new(seed: u64) -> Self {
+        // a state of zero would stay zero forever.
+        Self { state: seed.max(1) }
+    }
+}
+
+impl Iterator for XorShift {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<u64> {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        Some(self.state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_seed_same_numbers() {
+        let a: Vec<u64> = XorShift::new(2022).take(5).collect();
+        let b: Vec<u64> = XorShift::new(2022).take(5).collect();
+        assert_eq!(a, b);
+        assert_ne!(a, XorShift::new(2023).take(5).collect::<Vec<_>>());
+        assert!(XorShift::new(0).take(5).all(|n| n != 0));
+    }
+}
@@ -1,3 +1,14 @@
+// This module predates the lints the rest of the crate is checked with, and
+// is kept as it was written. `mismatched_lifetime_syntaxes` is newer than the
+// crate's rust-version, hence `unknown_lints`.
+#![allow(
+    unknown_lints,
+    mismatched_lifetime_syntaxes,
+    unused_variables,
+    clippy::comparison_to_empty,
+    clippy::bool_assert_comparison
+)]
+
 use nom::{
     branch::alt,
     bytes::complete::{tag, take_while},